@@ -1,471 +1,127 @@
-#![allow(unused)]
-use criterion::{black_box, criterion_group, criterion_main, Criterion, measurement::WallTime};
+use core::str::FromStr;
+use std::env;
+
+use criterion::{
+    black_box, criterion_main, measurement::{Measurement, WallTime}, BatchSize,
+    BenchmarkId, Criterion,
+};
 use criterion_perf_events::Perf;
 use perfcnt::linux::HardwareEventType as Hardware;
 use perfcnt::linux::PerfCounterBuilderLinux as Builder;
 
-use rust_sorts::ty::FloatOrd;
-use rust_sorts::{qsort::*, util::default_vec};
+use rust_sorts::dist::{Dist, Element};
+use rust_sorts::registry::{Algorithm, Sortable};
+use rust_sorts::ty::{DoubleOrd, FloatOrd, Record128, Record32, Record64};
+use rust_sorts::util::{seed, SEED_ENV};
+
+// The benchmark matrix is algorithms x distributions x element types x sizes,
+// every axis can be narrowed with a comma separated env var, e.g.
+//
+//   RUSORTS_ALGOS=hoare,hoare_block RUSORTS_DISTS=uniform,sawtooth RUSORTS_SIZES=1000 cargo bench
+//
+// Benchmark ids are `<algo>_<measurement>/<type>_<dist>/<size>`, so the usual
+// criterion filter works as well: `cargo bench -- 'hoare_time/f32_uniform'`
+const ALGOS_ENV: &str = "RUSORTS_ALGOS";
+const DISTS_ENV: &str = "RUSORTS_DISTS";
+const TYPES_ENV: &str = "RUSORTS_TYPES";
+const SIZES_ENV: &str = "RUSORTS_SIZES";
+const MEASUREMENTS_ENV: &str = "RUSORTS_MEASUREMENTS";
+
+const SMALL_SIZE: usize = 100;
+const MEDIUM_SIZE: usize = 1_000;
+const LARGE_SIZE: usize = 10_000;
+const HUGE_SIZE: usize = 100_000;
+const GIGANTIC_SIZE: usize = 1_000_000;
+const ENORMOUS_SIZE: usize = 10_000_000;
 
-use rand::{Rng, thread_rng};
+const SIZES: [usize; 6] = [SMALL_SIZE, MEDIUM_SIZE, LARGE_SIZE, HUGE_SIZE, GIGANTIC_SIZE, ENORMOUS_SIZE];
 
-macro_rules! gen_mostly_ascending {
-    ($func: ident, $typ: ty) => {
-        fn $func(len: usize) -> Vec<$typ> {
-            let mut rng = thread_rng();
-            let mut v: Vec<$typ> = (0..len).map(|x| <$typ>::from(x)).collect();
-            for _ in (0usize..).take_while(|x| x * x <= len) {
-                let x = rng.gen::<usize>() % len;
-                let y = rng.gen::<usize>() % len;
-                v.swap(x, y);
+const SAMPLE_SIZE: usize = 20;
+
+const MEASUREMENTS: [&str; 4] = ["time", "cpu_cycle", "cache_miss", "branch_miss"];
+
+/// parse a comma separated env var, `default` if it is not set
+fn env_list<T>(key: &str, default: &[T]) -> Vec<T>
+where
+    T: FromStr + Clone,
+    T::Err: std::fmt::Display,
+{
+    match env::var(key) {
+        Ok(s) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().unwrap_or_else(|e| panic!("{}: {}", key, e)))
+            .collect(),
+        Err(_) => default.to_vec(),
+    }
+}
+
+fn enabled(key: &str, name: &str, default: &[&str]) -> bool {
+    match env::var(key) {
+        Ok(s) => s.split(',').any(|n| n.trim() == name),
+        Err(_) => default.contains(&name),
+    }
+}
+
+fn bench_type<T, M>(c: &mut Criterion<M>, postfix: &str, type_name: &str)
+where
+    T: Sortable + Element + Clone,
+    M: Measurement,
+{
+    let algos = env_list(ALGOS_ENV, Algorithm::ALL);
+    let dists = env_list(DISTS_ENV, &[Dist::Uniform]);
+    let sizes = env_list(SIZES_ENV, &SIZES);
+    let seed = seed();
+
+    for algo in algos.into_iter().filter(|a| a.supports::<T>()) {
+        let mut group = c.benchmark_group(format!("{}{}", algo.name(), postfix));
+        for &dist in &dists {
+            for &size in &sizes {
+                let v: Vec<T> = dist.generate(size, seed);
+                group.bench_with_input(BenchmarkId::new(format!("{}_{}", type_name, dist), size), &v, |b, v|
+                    b.iter_batched_ref(|| v.clone(), |v| algo.sort(black_box(v)), BatchSize::LargeInput)
+                );
             }
-            v
         }
-    };
+        group.finish();
+    }
 }
 
-macro_rules! gen_mostly_descending {
-    ($func: ident, $typ: ty) => {
-        fn $func(len: usize) -> Vec<$typ> {
-            let mut rng = thread_rng();
-            let mut v: Vec<$typ> = (0..len).rev().map(|x| <$typ>::from(x)).collect();
-            for _ in (0usize..).take_while(|x| x * x <= len) {
-                let x = rng.gen::<usize>() % len;
-                let y = rng.gen::<usize>() % len;
-                v.swap(x, y);
+macro_rules! bench_types {
+    ($c: expr, $postfix: expr, $($t: ty => $name: expr),*) => {
+        $(
+            if enabled(TYPES_ENV, $name, &TYPES) {
+                bench_type::<$t, _>($c, $postfix, $name);
             }
-            v
-        }
+        )*
     };
 }
 
-gen_mostly_ascending!(mostly_ascending_f32, FloatOrd);
-gen_mostly_descending!(mostly_descending_f32, FloatOrd);
+// cheap to compare and move, up to 128 byte records and heap allocated strings
+const TYPES: [&str; 11] = ["f32", "f64", "u32", "i32", "u64", "i64", "pair", "rec32", "rec64", "rec128", "string"];
+
+fn bench_all_types<M: Measurement>(c: &mut Criterion<M>, postfix: &str) {
+    bench_types!(c, postfix,
+        FloatOrd => "f32",
+        DoubleOrd => "f64",
+        u32 => "u32",
+        i32 => "i32",
+        u64 => "u64",
+        i64 => "i64",
+        (u64, u64) => "pair",
+        Record32 => "rec32",
+        Record64 => "rec64",
+        Record128 => "rec128",
+        String => "string"
+    );
+}
 
 macro_rules! gen_bench_for_measurement {
     ($func: ident, $typ: ty, $postfix: expr) => {
         pub fn $func(c: &mut Criterion<$typ>) {
-            let mut name = String::from("1_pivot_hoare");
-            name.push_str($postfix);
-            let mut g1_hoare = c.benchmark_group(name);
-        
-            g1_hoare.bench_function("1_pivot_f32_hoare_sort_small_random", |b| 
-                b.iter(|| quick_sort_hoare_partition(black_box(&mut default_vec::<FloatOrd>(SMALL_SIZE))))
-            );
-            // g1_hoare.bench_function("1_pivot_f32_hoare_sort_small_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition(black_box(&mut mostly_ascending_f32(SMALL_SIZE))))
-            // );
-            // g1_hoare.bench_function("1_pivot_f32_hoare_sort_small_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition(black_box(&mut mostly_descending_f32(SMALL_SIZE))))
-            // );
-        
-            g1_hoare.bench_function("1_pivot_f32_hoare_sort_medium_random", |b| 
-                b.iter(|| quick_sort_hoare_partition(black_box(&mut default_vec::<FloatOrd>(MEDIUM_SIZE))))
-            );
-            // g1_hoare.bench_function("1_pivot_f32_hoare_sort_medium_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition(black_box(&mut mostly_ascending_f32(MEDIUM_SIZE))))
-            // );
-            // g1_hoare.bench_function("1_pivot_f32_hoare_sort_medium_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition(black_box(&mut mostly_descending_f32(MEDIUM_SIZE))))
-            // );
-        
-            g1_hoare.bench_function("1_pivot_f32_hoare_sort_large_random", |b| 
-                b.iter(|| quick_sort_hoare_partition(black_box(&mut default_vec::<FloatOrd>(LARGE_SIZE))))
-            );
-            // g1_hoare.bench_function("1_pivot_f32_hoare_sort_large_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition(black_box(&mut mostly_ascending_f32(LARGE_SIZE))))
-            // );
-            // g1_hoare.bench_function("1_pivot_f32_hoare_sort_large_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition(black_box(&mut mostly_descending_f32(LARGE_SIZE))))
-            // );
-
-            g1_hoare.bench_function("1_pivot_f32_hoare_sort_huge_random", |b| 
-                b.iter(|| quick_sort_hoare_partition(black_box(&mut default_vec::<FloatOrd>(HUGE_SIZE))))
-            );
-
-            g1_hoare.bench_function("1_pivot_f32_hoare_sort_gigantic_random", |b| 
-                b.iter(|| quick_sort_hoare_partition(black_box(&mut default_vec::<FloatOrd>(GIGANTIC_SIZE))))
-            );
-
-            g1_hoare.bench_function("1_pivot_f32_hoare_sort_enormous_random", |b| 
-                b.iter(|| quick_sort_hoare_partition(black_box(&mut default_vec::<FloatOrd>(ENORMOUS_SIZE))))
-            );
-
-            g1_hoare.finish();
-        
-            let mut name = String::from("1_pivot_lomuto");
-            name.push_str($postfix);
-            let mut g1_lomuto = c.benchmark_group(name);
-            
-            g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_small_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition(black_box(&mut default_vec::<FloatOrd>(SMALL_SIZE))))
-            );
-            // g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_small_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition(black_box(&mut mostly_ascending_f32(SMALL_SIZE))))
-            // );
-            // g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_small_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition(black_box(&mut mostly_descending_f32(SMALL_SIZE))))
-            // );
-        
-            g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_medium_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition(black_box(&mut default_vec::<FloatOrd>(MEDIUM_SIZE))))
-            );
-            // g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_medium_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition(black_box(&mut mostly_ascending_f32(MEDIUM_SIZE))))
-            // );
-            // g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_medium_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition(black_box(&mut mostly_descending_f32(MEDIUM_SIZE))))
-            // );
-        
-            g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_large_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition(black_box(&mut default_vec::<FloatOrd>(LARGE_SIZE))))
-            );
-            // g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_large_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition(black_box(&mut mostly_ascending_f32(LARGE_SIZE))))
-            // );
-            // g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_large_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition(black_box(&mut mostly_descending_f32(LARGE_SIZE))))
-            // );
-
-            g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_huge_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition(black_box(&mut default_vec::<FloatOrd>(HUGE_SIZE))))
-            );
-
-            g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_gigantic_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition(black_box(&mut default_vec::<FloatOrd>(GIGANTIC_SIZE))))
-            );
-
-            g1_lomuto.bench_function("1_pivot_f32_lomuto_sort_enormous_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition(black_box(&mut default_vec::<FloatOrd>(ENORMOUS_SIZE))))
-            );
-        
-            g1_lomuto.finish();
-        
-            let mut name = String::from("2_pivot");
-            name.push_str($postfix);
-            let mut g2 = c.benchmark_group(name);
-        
-            g2.bench_function("2_pivot_f32_sort_small_random", |b| 
-                b.iter(|| double_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(SMALL_SIZE))))
-            );
-            // g2.bench_function("2_pivot_f32_sort_small_mostly_ascending", |b| 
-            //     b.iter(|| double_pivot_quicksort(black_box(&mut mostly_ascending_f32(SMALL_SIZE))))
-            // );
-            // g2.bench_function("2_pivot_f32_sort_small_mostly_descending", |b| 
-            //     b.iter(|| double_pivot_quicksort(black_box(&mut mostly_descending_f32(SMALL_SIZE))))
-            // );
-        
-            g2.bench_function("2_pivot_f32_sort_medium_random", |b| 
-                b.iter(|| double_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(MEDIUM_SIZE))))
-            );
-            // g2.bench_function("2_pivot_f32_sort_medium_mostly_ascending", |b| 
-            //     b.iter(|| double_pivot_quicksort(black_box(&mut mostly_ascending_f32(MEDIUM_SIZE))))
-            // );
-            // g2.bench_function("2_pivot_f32_sort_medium_mostly_descending", |b| 
-            //     b.iter(|| double_pivot_quicksort(black_box(&mut mostly_descending_f32(MEDIUM_SIZE))))
-            // );
-        
-            g2.bench_function("2_pivot_f32_sort_large_random", |b| 
-                b.iter(|| double_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(LARGE_SIZE))))
-            );
-            // g2.bench_function("2_pivot_f32_sort_large_mostly_ascending", |b| 
-            //     b.iter(|| double_pivot_quicksort(black_box(&mut mostly_ascending_f32(LARGE_SIZE))))
-            // );
-            // g2.bench_function("2_pivot_f32_sort_large_mostly_descending", |b| 
-            //     b.iter(|| double_pivot_quicksort(black_box(&mut mostly_descending_f32(LARGE_SIZE))))
-            // );
-
-            g2.bench_function("2_pivot_f32_sort_huge_random", |b| 
-                b.iter(|| double_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(HUGE_SIZE))))
-            );
-
-            g2.bench_function("2_pivot_f32_sort_gigantic_random", |b| 
-                b.iter(|| double_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(GIGANTIC_SIZE))))
-            );
-
-            g2.bench_function("2_pivot_f32_sort_enormous_random", |b| 
-                b.iter(|| double_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(ENORMOUS_SIZE))))
-            );
-            
-            g2.finish();
-        
-            let mut name = String::from("3_pivot");
-            name.push_str($postfix);
-            let mut g3 = c.benchmark_group(name);
-        
-            g3.bench_function("3_pivot_f32_sort_small_random", |b| 
-                b.iter(|| triple_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(SMALL_SIZE))))
-            );
-            // g3.bench_function("3_pivot_f32_sort_small_mostly_ascending", |b| 
-            //     b.iter(|| triple_pivot_quicksort(black_box(&mut mostly_ascending_f32(SMALL_SIZE))))
-            // );
-            // g3.bench_function("3_pivot_f32_sort_small_mostly_descending", |b| 
-            //     b.iter(|| triple_pivot_quicksort(black_box(&mut mostly_descending_f32(SMALL_SIZE))))
-            // );
-        
-            g3.bench_function("3_pivot_f32_sort_medium_random", |b| 
-                b.iter(|| triple_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(MEDIUM_SIZE))))
-            );
-            // g3.bench_function("3_pivot_f32_sort_medium_mostly_ascending", |b| 
-            //     b.iter(|| triple_pivot_quicksort(black_box(&mut mostly_ascending_f32(MEDIUM_SIZE))))
-            // );
-            // g3.bench_function("3_pivot_f32_sort_medium_mostly_descending", |b| 
-            //     b.iter(|| triple_pivot_quicksort(black_box(&mut mostly_descending_f32(MEDIUM_SIZE))))
-            // );
-        
-            g3.bench_function("3_pivot_f32_sort_large_random", |b| 
-                b.iter(|| triple_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(LARGE_SIZE))))
-            );
-            // g3.bench_function("3_pivot_f32_sort_large_mostly_ascending", |b| 
-            //     b.iter(|| triple_pivot_quicksort(black_box(&mut mostly_ascending_f32(LARGE_SIZE))))
-            // );
-            // g3.bench_function("3_pivot_f32_sort_large_mostly_descending", |b| 
-            //     b.iter(|| triple_pivot_quicksort(black_box(&mut mostly_descending_f32(LARGE_SIZE))))
-            // );
-
-            g3.bench_function("3_pivot_f32_sort_huge_random", |b| 
-                b.iter(|| triple_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(HUGE_SIZE))))
-            );
-
-            g3.bench_function("3_pivot_f32_sort_gigantic_random", |b| 
-                b.iter(|| triple_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(GIGANTIC_SIZE))))
-            );
-
-            g3.bench_function("3_pivot_f32_sort_enormous_random", |b| 
-                b.iter(|| triple_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(ENORMOUS_SIZE))))
-            );
-        
-            g3.finish();
-        
-            let mut name = String::from("4_pivot");
-            name.push_str($postfix);
-            let mut g4 = c.benchmark_group(name);
-        
-            g4.bench_function("4_pivot_f32_sort_small_random", |b| 
-                b.iter(|| quad_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(SMALL_SIZE))))
-            );
-            // g4.bench_function("4_pivot_f32_sort_small_mostly_ascending", |b| 
-            //     b.iter(|| quad_pivot_quicksort(black_box(&mut mostly_ascending_f32(SMALL_SIZE))))
-            // );
-            // g4.bench_function("4_pivot_f32_sort_small_mostly_descending", |b| 
-            //     b.iter(|| quad_pivot_quicksort(black_box(&mut mostly_descending_f32(SMALL_SIZE))))
-            // );
-        
-            g4.bench_function("4_pivot_f32_sort_medium_random", |b| 
-                b.iter(|| quad_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(MEDIUM_SIZE))))
-            );
-            // g4.bench_function("4_pivot_f32_sort_medium_mostly_ascending", |b| 
-            //     b.iter(|| quad_pivot_quicksort(black_box(&mut mostly_ascending_f32(MEDIUM_SIZE))))
-            // );
-            // g4.bench_function("4_pivot_f32_sort_medium_mostly_descending", |b| 
-            //     b.iter(|| quad_pivot_quicksort(black_box(&mut mostly_descending_f32(MEDIUM_SIZE))))
-            // );
-        
-            g4.bench_function("4_pivot_f32_sort_large_random", |b| 
-                b.iter(|| quad_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(LARGE_SIZE))))
-            );
-            // g4.bench_function("4_pivot_f32_sort_large_mostly_ascending", |b| 
-            //     b.iter(|| quad_pivot_quicksort(black_box(&mut mostly_ascending_f32(LARGE_SIZE))))
-            // );
-            // g4.bench_function("4_pivot_f32_sort_large_mostly_descending", |b| 
-            //     b.iter(|| quad_pivot_quicksort(black_box(&mut mostly_descending_f32(LARGE_SIZE))))
-            // );
-
-            g4.bench_function("4_pivot_f32_sort_huge_random", |b| 
-                b.iter(|| quad_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(HUGE_SIZE))))
-            );
-
-            g4.bench_function("4_pivot_f32_sort_gigantic_random", |b| 
-                b.iter(|| quad_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(GIGANTIC_SIZE))))
-            );
-
-            g4.bench_function("4_pivot_f32_sort_enormous_random", |b| 
-                b.iter(|| quad_pivot_quicksort(black_box(&mut default_vec::<FloatOrd>(ENORMOUS_SIZE))))
-            );
-        
-            g4.finish();
-
-            let mut name = String::from("block_partition_hoare");
-            name.push_str($postfix);
-            let mut h = c.benchmark_group(name);
-        
-            h.bench_function("1_pivot_f32_hoare_small_random", |b| 
-                b.iter(|| quick_sort_hoare_partition_block(black_box(&mut default_vec::<FloatOrd>(SMALL_SIZE))))
-            );
-            // h.bench_function("1_pivot_f32_hoare_small_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition_block(black_box(&mut mostly_ascending_f32(SMALL_SIZE))))
-            // );
-            // h.bench_function("1_pivot_f32_hoare_small_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition_block(black_box(&mut mostly_descending_f32(SMALL_SIZE))))
-            // );
-        
-            h.bench_function("1_pivot_f32_hoare_medium_random", |b| 
-                b.iter(|| quick_sort_hoare_partition_block(black_box(&mut default_vec::<FloatOrd>(MEDIUM_SIZE))))
-            );
-            // h.bench_function("1_pivot_f32_hoare_medium_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition_block(black_box(&mut mostly_ascending_f32(MEDIUM_SIZE))))
-            // );
-            // h.bench_function("1_pivot_f32_hoare_medium_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition_block(black_box(&mut mostly_descending_f32(MEDIUM_SIZE))))
-            // );
-        
-            h.bench_function("1_pivot_f32_hoare_large_random", |b| 
-                b.iter(|| quick_sort_hoare_partition_block(black_box(&mut default_vec::<FloatOrd>(LARGE_SIZE))))
-            );
-            // h.bench_function("1_pivot_f32_hoare_large_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition_block(black_box(&mut mostly_ascending_f32(LARGE_SIZE))))
-            // );
-            // h.bench_function("1_pivot_f32_hoare_large_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_hoare_partition_block(black_box(&mut mostly_descending_f32(LARGE_SIZE))))
-            // );
-
-            h.bench_function("1_pivot_f32_hoare_huge_random", |b| 
-                b.iter(|| quick_sort_hoare_partition_block(black_box(&mut default_vec::<FloatOrd>(HUGE_SIZE))))
-            );
-
-            h.bench_function("1_pivot_f32_hoare_gigantic_random", |b| 
-                b.iter(|| quick_sort_hoare_partition_block(black_box(&mut default_vec::<FloatOrd>(GIGANTIC_SIZE))))
-            );
-
-            h.bench_function("1_pivot_f32_hoare_enormous_random", |b| 
-                b.iter(|| quick_sort_hoare_partition_block(black_box(&mut default_vec::<FloatOrd>(ENORMOUS_SIZE))))
-            );
-        
-            h.finish();
-        
-            let mut name = String::from("block_partition_lomuto");
-            name.push_str($postfix);
-            let mut l = c.benchmark_group(name);
-        
-            l.bench_function("1_pivot_f32_lomuto_small_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(SMALL_SIZE))))
-            );
-            // l.bench_function("1_pivot_f32_lomuto_small_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut mostly_ascending_f32(SMALL_SIZE))))
-            // );
-            // l.bench_function("1_pivot_f32_lomuto_small_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut mostly_descending_f32(SMALL_SIZE))))
-            // );
-        
-            l.bench_function("1_pivot_f32_lomuto_medium_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(MEDIUM_SIZE))))
-            );
-            // l.bench_function("1_pivot_f32_lomuto_medium_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut mostly_ascending_f32(MEDIUM_SIZE))))
-            // );
-            // l.bench_function("1_pivot_f32_lomuto_medium_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut mostly_descending_f32(MEDIUM_SIZE))))
-            // );
-        
-            l.bench_function("1_pivot_f32_lomuto_large_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(LARGE_SIZE))))
-            );
-            // l.bench_function("1_pivot_f32_lomuto_large_mostly_ascending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut mostly_ascending_f32(LARGE_SIZE))))
-            // );
-            // l.bench_function("1_pivot_f32_lomuto_large_mostly_descending", |b| 
-            //     b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut mostly_descending_f32(LARGE_SIZE))))
-            // );
-
-            l.bench_function("1_pivot_f32_lomuto_huge_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(HUGE_SIZE))))
-            );
-
-            l.bench_function("1_pivot_f32_lomuto_gigantic_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(GIGANTIC_SIZE))))
-            );
-
-            l.bench_function("1_pivot_f32_lomuto_enormous_random", |b| 
-                b.iter(|| quick_sort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(ENORMOUS_SIZE))))
-            );
-        
-            l.bench_function("2_pivot_f32_lomuto_small_random", |b| 
-                b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(SMALL_SIZE))))
-            );
-            // l.bench_function("2_pivot_f32_lomuto_small_mostly_ascending", |b| 
-            //     b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut mostly_ascending_f32(SMALL_SIZE))))
-            // );
-            // l.bench_function("2_pivot_f32_lomuto_small_mostly_descending", |b| 
-            //     b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut mostly_descending_f32(SMALL_SIZE))))
-            // );
-        
-            l.bench_function("2_pivot_f32_lomuto_medium_random", |b| 
-                b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(MEDIUM_SIZE))))
-            );
-            // l.bench_function("2_pivot_f32_lomuto_medium_mostly_ascending", |b| 
-            //     b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut mostly_ascending_f32(MEDIUM_SIZE))))
-            // );
-            // l.bench_function("2_pivot_f32_lomuto_medium_mostly_descending", |b| 
-            //     b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut mostly_descending_f32(MEDIUM_SIZE))))
-            // );
-        
-            l.bench_function("2_pivot_f32_lomuto_large_random", |b| 
-                b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(LARGE_SIZE))))
-            );
-            // l.bench_function("2_pivot_f32_lomuto_large_mostly_ascending", |b| 
-            //     b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut mostly_ascending_f32(LARGE_SIZE))))
-            // );
-            // l.bench_function("2_pivot_f32_lomuto_large_mostly_descending", |b| 
-            //     b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut mostly_descending_f32(LARGE_SIZE))))
-            // );
-
-            l.bench_function("2_pivot_f32_lomuto_huge_random", |b| 
-                b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(HUGE_SIZE))))
-            );
-
-            l.bench_function("2_pivot_f32_lomuto_gigantic_random", |b| 
-                b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(GIGANTIC_SIZE))))
-            );
-
-            l.bench_function("2_pivot_f32_lomuto_enormous_random", |b| 
-                b.iter(|| double_pivot_quicksort_lomuto_partition_block(black_box(&mut default_vec::<FloatOrd>(ENORMOUS_SIZE))))
-            );
-
-        
-            l.finish();
-        
-            let mut name = String::from("block_partition_new");
-            name.push_str($postfix);
-            let mut n = c.benchmark_group(name);
-        
-            n.bench_function("2_pivot_f32_new_small_random", |b| 
-                b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut default_vec::<FloatOrd>(SMALL_SIZE))))
-            );
-            // n.bench_function("2_pivot_f32_new_small_mostly_ascending", |b| 
-            //     b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut mostly_ascending_f32(SMALL_SIZE))))
-            // );
-            // n.bench_function("2_pivot_f32_new_small_mostly_descending", |b| 
-            //     b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut mostly_descending_f32(SMALL_SIZE))))
-            // );
-        
-            n.bench_function("2_pivot_f32_new_medium_random", |b| 
-                b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut default_vec::<FloatOrd>(MEDIUM_SIZE))))
-            );
-        
-            n.bench_function("2_pivot_f32_new_large_random", |b| 
-                b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut default_vec::<FloatOrd>(LARGE_SIZE))))
-            );
-            // n.bench_function("2_pivot_f32_new_large_mostly_ascending", |b| 
-            //     b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut mostly_ascending_f32(LARGE_SIZE))))
-            // );
-            // n.bench_function("2_pivot_f32_new_large_mostly_descending", |b| 
-            //     b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut mostly_descending_f32(LARGE_SIZE))))
-            // );
-
-            n.bench_function("2_pivot_f32_new_huge_random", |b| 
-                b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut default_vec::<FloatOrd>(HUGE_SIZE))))
-            );
-
-            n.bench_function("2_pivot_f32_new_gigantic_random", |b| 
-                b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut default_vec::<FloatOrd>(GIGANTIC_SIZE))))
-            );
-
-            n.bench_function("2_pivot_f32_new_enormous_random", |b| 
-                b.iter(|| double_pivot_quicksort_new_partition_block(black_box(&mut default_vec::<FloatOrd>(ENORMOUS_SIZE))))
-            );
-        
-            n.finish();
-        
+            println!("{}: input generated with {}={}", stringify!($func), SEED_ENV, seed());
+            bench_all_types(c, $postfix);
         }
     };
 }
@@ -476,36 +132,44 @@ gen_bench_for_measurement!(cache_miss_bench, Perf, "_cache_miss");
 gen_bench_for_measurement!(branch_miss_bench, Perf, "_branch_miss");
 
 
-const SMALL_SIZE: usize = 100;
-const MEDIUM_SIZE: usize = 1_000;
-const LARGE_SIZE: usize = 10_000;
-const HUGE_SIZE: usize = 100_000;
-const GIGANTIC_SIZE: usize = 1_000_000;
-const ENORMOUS_SIZE: usize = 10_000_000;
-
-const SAMPLE_SIZE: usize = 20;
-
+// like `criterion_group!`, but the config (which opens the perf counter) is only
+// built when the measurement is enabled in `RUSORTS_MEASUREMENTS`
+macro_rules! measurement_group {
+    (name = $name: ident; measurement = $m: expr; config = $config: expr; targets = $target: path) => {
+        pub fn $name() {
+            if !enabled(MEASUREMENTS_ENV, $m, &MEASUREMENTS) {
+                return;
+            }
+            let mut criterion = $config.configure_from_args();
+            $target(&mut criterion);
+        }
+    };
+}
 
-criterion_group!(
+measurement_group!(
     name = time;
+    measurement = "time";
     config = Criterion::default().sample_size(SAMPLE_SIZE);
     targets = time_bench
 );
 
-criterion_group!(
+measurement_group!(
     name = cpu_cycles;
+    measurement = "cpu_cycle";
     config = Criterion::default().sample_size(SAMPLE_SIZE).with_measurement(Perf::new(Builder::from_hardware_event(Hardware::CPUCycles)));
     targets = cpu_cycle_bench
 );
 
-criterion_group!(
+measurement_group!(
     name = branch_misses;
+    measurement = "branch_miss";
     config = Criterion::default().sample_size(SAMPLE_SIZE).with_measurement(Perf::new(Builder::from_hardware_event(Hardware::BranchMisses)));
     targets = branch_miss_bench
 );
 
-criterion_group!(
+measurement_group!(
     name = cache_misses;
+    measurement = "cache_miss";
     config = Criterion::default().sample_size(SAMPLE_SIZE).with_measurement(Perf::new(Builder::from_hardware_event(Hardware::CacheMisses)));
     targets = cache_miss_bench
 );
@@ -0,0 +1,108 @@
+/* sorts random buffers of every type with every algorithm through the C API
+ *
+ *   cargo build --release --no-default-features
+ *   cc -O2 -Iinclude c/test_rusorts.c -Ltarget/release -lrust_sorts -o target/test_rusorts
+ *   LD_LIBRARY_PATH=target/release target/test_rusorts
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "rusorts.h"
+
+#define N 100000
+
+static int failures = 0;
+
+static void check(int ok, const char *what, uint32_t algo) {
+    if (!ok) {
+        printf("FAIL %s %s\n", what, rusorts_algorithm_name(algo));
+        failures++;
+    }
+}
+
+/* sort a random buffer of `type`, SIMD algorithms have to refuse everything but float */
+#define TEST_SORT(type, fn, rand_value)                                          \
+    do {                                                                         \
+        type *v = malloc(N * sizeof(type));                                      \
+        size_t i;                                                                \
+        int code, sorted = 1;                                                    \
+        for (i = 0; i < N; i++)                                                  \
+            v[i] = (rand_value);                                                 \
+        code = fn(v, N, algo);                                                   \
+        if (code == RUSORTS_ERR_UNSUPPORTED) {                                   \
+            check(simd, #fn " refused", algo);                                   \
+        } else {                                                                 \
+            check(code == RUSORTS_OK, #fn " returned an error", algo);           \
+            for (i = 1; i < N; i++)                                              \
+                sorted &= v[i - 1] <= v[i];                                      \
+            check(sorted, #fn " left the buffer unsorted", algo);                \
+        }                                                                        \
+        free(v);                                                                 \
+    } while (0)
+
+/* an odd sized record, to check rusorts_qsort against libc qsort */
+struct record {
+    double key;
+    char name[13];
+};
+
+static int by_key(const void *a, const void *b) {
+    double x = ((const struct record *)a)->key, y = ((const struct record *)b)->key;
+    return (x > y) - (x < y);
+}
+
+static void test_qsort(void) {
+    struct record *v = malloc(N * sizeof(struct record)), *expected = malloc(N * sizeof(struct record));
+    size_t i;
+    int same = 1;
+    for (i = 0; i < N; i++) {
+        /* distinct keys, libc qsort isn't stable either */
+        v[i].key = (double)rand() / RAND_MAX + (double)i / N / RAND_MAX;
+        snprintf(v[i].name, sizeof v[i].name, "record %zu", i);
+    }
+    memcpy(expected, v, N * sizeof(struct record));
+    qsort(expected, N, sizeof(struct record), by_key);
+    rusorts_qsort(v, N, sizeof(struct record), by_key);
+    for (i = 0; i < N; i++)
+        same &= v[i].key == expected[i].key && strcmp(v[i].name, expected[i].name) == 0;
+    check(same, "rusorts_qsort differs from qsort", RUSORTS_HOARE_BLOCK);
+    free(v);
+    free(expected);
+}
+
+int main(void) {
+    uint32_t algo;
+    int32_t small[] = {3, 1, 2};
+
+    if (rusorts_algorithm_count() != RUSORTS_ALGORITHM_COUNT) {
+        printf("FAIL the library has %u algorithms, the header %d\n", rusorts_algorithm_count(),
+               RUSORTS_ALGORITHM_COUNT);
+        return 1;
+    }
+    srand(42);
+    for (algo = 0; algo < RUSORTS_ALGORITHM_COUNT; algo++) {
+        const char *name = rusorts_algorithm_name(algo);
+        int simd = algo >= RUSORTS_QUAD_PIVOT_SIMD && algo <= RUSORTS_OCTAL_PIVOT_SIMD;
+
+        TEST_SORT(int32_t, rusorts_sort_i32, rand() - RAND_MAX / 2);
+        TEST_SORT(uint32_t, rusorts_sort_u32, (uint32_t)rand() * 2u);
+        TEST_SORT(int64_t, rusorts_sort_i64, ((int64_t)rand() << 31 | rand()) - ((int64_t)1 << 61));
+        TEST_SORT(float, rusorts_sort_f32, (float)rand() / RAND_MAX - 0.5f);
+        TEST_SORT(double, rusorts_sort_f64, (double)rand() / RAND_MAX - 0.5);
+        check(rusorts_sort_f32(NULL, 0, algo) == RUSORTS_OK, "empty buffer", algo);
+        printf("ok %s\n", name);
+    }
+
+    check(rusorts_sort_i32(small, 3, RUSORTS_ALGORITHM_COUNT) == RUSORTS_ERR_ALGORITHM, "unknown algorithm",
+          0);
+    check(rusorts_sort_i32(NULL, 3, RUSORTS_HOARE_BLOCK) == RUSORTS_ERR_NULL, "NULL data", 0);
+    check(rusorts_algorithm_name(RUSORTS_ALGORITHM_COUNT) == NULL, "name out of range", 0);
+    test_qsort();
+    if (failures) {
+        printf("%d failures\n", failures);
+        return 1;
+    }
+    printf("all passed\n");
+    return 0;
+}
@@ -0,0 +1,65 @@
+/* generated by `src/ffi.rs`, run `RUSORTS_BLESS=1 cargo test ffi` after changing it */
+#ifndef RUSORTS_H
+#define RUSORTS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* return codes of the rusorts_sort_* functions */
+#define RUSORTS_OK 0 /* sorted */
+#define RUSORTS_ERR_NULL -1 /* data is NULL and len is not 0 */
+#define RUSORTS_ERR_ALGORITHM -2 /* not a RusortsAlgorithm */
+#define RUSORTS_ERR_UNSUPPORTED -3 /* the algorithm can't sort this element type */
+#define RUSORTS_ERR_PANIC -4 /* the sort failed, the data is left in some order */
+
+typedef enum RusortsAlgorithm {
+    RUSORTS_STD_UNSTABLE = 0, /* Std Unstable */
+    RUSORTS_PAR_PDQSORT = 1, /* Parallel PDQSort */
+    RUSORTS_PAR_CRUMSORT = 2, /* Parallel Crumsort */
+    RUSORTS_LOMUTO = 3, /* 1-Pivot Lomuto */
+    RUSORTS_HOARE = 4, /* 1-Pivot Hoare */
+    RUSORTS_LOMUTO_BLOCK = 5, /* 1-Pivot Lomuto Block Partition */
+    RUSORTS_HOARE_BLOCK = 6, /* 1-Pivot Hoare Block Partition */
+    RUSORTS_DUAL_PIVOT = 7, /* 2-Pivot Yaro */
+    RUSORTS_DUAL_PIVOT_LOMUTO_BLOCK = 8, /* 2-Pivot Lomuto Block */
+    RUSORTS_DUAL_PIVOT_NEW_BLOCK = 9, /* 2-Pivot New Block */
+    RUSORTS_TRIPLE_PIVOT = 10, /* 3-Pivot Kush */
+    RUSORTS_QUAD_PIVOT = 11, /* 4-Pivot */
+    RUSORTS_QUAD_PIVOT_SIMD = 12, /* 4-Pivot SIMD */
+    RUSORTS_PENTA_PIVOT_SIMD = 13, /* 5-Pivot SIMD */
+    RUSORTS_HEXA_PIVOT_SIMD = 14, /* 6-Pivot SIMD */
+    RUSORTS_HEPTA_PIVOT_SIMD = 15, /* 7-Pivot SIMD */
+    RUSORTS_OCTAL_PIVOT_SIMD = 16, /* 8-Pivot SIMD */
+    RUSORTS_ALGORITHM_COUNT = 17
+} RusortsAlgorithm;
+
+/* Sort `len` elements at `data` ascending in place with `algorithm`, a
+ * RusortsAlgorithm. Returns RUSORTS_OK or one of the RUSORTS_ERR_* codes,
+ * the SIMD algorithms only sort float. Floats are ordered like the
+ * `FloatOrd` wrapper of the crate, a NaN makes the SIMD algorithms fail
+ * with RUSORTS_ERR_PANIC. A library built with panic = "abort", like the
+ * release profile, aborts the process instead */
+int rusorts_sort_i32(int32_t *data, size_t len, uint32_t algorithm);
+int rusorts_sort_u32(uint32_t *data, size_t len, uint32_t algorithm);
+int rusorts_sort_i64(int64_t *data, size_t len, uint32_t algorithm);
+int rusorts_sort_f32(float *data, size_t len, uint32_t algorithm);
+int rusorts_sort_f64(double *data, size_t len, uint32_t algorithm);
+
+/* name of the algorithm, NULL if out of range, the string is static */
+const char *rusorts_algorithm_name(uint32_t algorithm);
+uint32_t rusorts_algorithm_count(void);
+
+/* drop-in for qsort, an in-place introsort over the block partition of
+ * RUSORTS_HOARE_BLOCK, O(n log n) on any input. The only extra memory is one
+ * element of scratch. A panic aborts the process */
+void rusorts_qsort(void *base, size_t n, size_t size, int (*cmp)(const void *, const void *));
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUSORTS_H */
@@ -37,6 +37,15 @@ titles = [
 ]
 
 
+# times of the hand made CSVs carry a unit, the exported ones are bare μs
+TIME_UNITS = {'ns': 1e-3, 'μs': 1.0, 'us': 1.0, 'ms': 1e3, 's': 1e6}
+
+
+def value(field: str) -> float:
+    number, _, unit = field.strip().partition(' ')
+    return float(number) * TIME_UNITS[unit] if unit else float(number)
+
+
 def plot_data(filename: str, title: str, unit: str):
     # Read categories from the CSV file
     categories = set()
@@ -54,8 +63,8 @@ def plot_data(filename: str, title: str, unit: str):
         for row in reader:
             category = row['Method']
             size = int(row['Size'])
-            mean = float(row['Mean'])
-            sd = float(row['SD'])
+            mean = value(row['Mean'])
+            sd = value(row['SD'])
             data[category]['Size'].append(size)
             data[category]['Mean'].append(mean)
             data[category]['SD'].append(sd)
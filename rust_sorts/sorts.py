@@ -1,6 +1,3 @@
-from typing import Callable
-import copy
-import random
 import os
 
 import rust_sorts
@@ -16,35 +13,34 @@ TIME_STATS_N = [
     10_000_000,
 ]
 SORT_FUNCS = {
-    "Parallel Crumsort": rust_sorts.par_crumsort,
-    "Parellel PDQSort": rust_sorts.par_pdqsort,
-    "Quicksort hoare block partition": rust_sorts.single_pivot_quicksort_hoare_block_partition,
-    "Quicksort 4 Pivots": rust_sorts.quadro_pivot_quicksort,
+    "Parallel Crumsort": "par_crumsort",
+    "Parellel PDQSort": "par_pdqsort",
+    "Quicksort hoare block partition": "hoare_block",
+    "Quicksort 4 Pivots": "quad_pivot",
 }
 
 
-def time_sort(sort_name: str, sort_func: Callable[[float], int], to_sort: list[float], stats: list[float]) -> None:
-    copied = copy.deepcopy(to_sort)
-    time_per_call = sum([sort_func(copied)[1] for _ in range(RUNS)]) / RUNS / 1_000
-    print(f"{sort_name} per call: {time_per_call:.2f} μs")
-    stats.append(time_per_call)
-
-
-def run_sorts_with_size(n: int):
-    print(f"Running sorts with n = {n}")
-    to_sort: list[float] = [random.random() for _ in range(n)]
-
-    stats: list[float] = []
-    for sort_name, sort_func in SORT_FUNCS.items():
-        time_sort(sort_name, sort_func, to_sort, stats)
-
-    TIME_STATS.append(stats)
-    print('---')
+def run_sorts() -> None:
+    # the data is generated in Rust, only the sorts are timed
+    result = rust_sorts.benchmark(
+        algorithms=list(SORT_FUNCS.values()), sizes=TIME_STATS_N, distribution="uniform", runs=RUNS
+    )
+    names = {algorithm: sort_name for sort_name, algorithm in SORT_FUNCS.items()}
+    for n in TIME_STATS_N:
+        print(f"Running sorts with n = {n}")
+        stats: list[float] = []
+        for i, size in enumerate(result["Size"]):
+            if size != n:
+                continue
+            time_per_call = result["Mean"][i]
+            print(f"{names[result['Algorithm'][i]]} per call: {time_per_call:.2f} ± {result['SD'][i]:.2f} μs")
+            stats.append(time_per_call)
+        TIME_STATS.append(stats)
+        print('---')
 
 
 def main():
-    for n in TIME_STATS_N:
-        run_sorts_with_size(n)
+    run_sorts()
 
     # draw plot of results
     import matplotlib
@@ -0,0 +1,245 @@
+//! Time the sorts of the registry over a matrix of algorithms, distributions,
+//! element types and sizes without criterion, with perf counters when they
+//! are available, and write the result CSVs (times in μs) and `results.json` like `rusorts-export`
+//!
+//! ```shell
+//! cargo run --release --bin rusorts-bench -- --algos hoare,hoare_block --sizes 1000,100000 --types f32,u64
+//! # the same data in numpy and for a second run
+//! cargo run --release --bin rusorts-bench -- --dists zipf --sizes 100000 --dump data
+//! cargo run --release --bin rusorts-bench -- --input data/f32_zipf_100000.npy
+//! ```
+use core::str::FromStr;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use rust_sorts::dist::{Dist, Element};
+use rust_sorts::export::{export, Measurement, Row};
+use rust_sorts::format::{LeBytes, Type};
+use rust_sorts::npy;
+use rust_sorts::perf::{self, Event};
+use rust_sorts::registry::{Algorithm, Sortable};
+use rust_sorts::timing::measure_sort;
+use rust_sorts::ty::{DoubleOrd, FloatOrd, Record128, Record32, Record64};
+use rust_sorts::util::seed;
+
+const USAGE: &str = "usage: rusorts-bench [OPTIONS]
+
+every option takes a comma separated list
+
+  --algos NAMES        algorithms of the registry, default all
+  --dists NAMES        distributions, default uniform
+  --types NAMES        f32 f64 u32 i32 u64 i64 pair rec32 rec64 rec128 string, default f32
+  --sizes N            default 1000,10000,100000
+  --events NAMES       perf events, default cpu_cycle,cache_miss,branch_miss, none for only the time
+                       (parallel algorithms only get the time, perf misses their worker threads)
+  --runs N             timed runs per sort, default 10
+  --warmup N           untimed runs before, default 1
+  --seed N             default RUSORTS_SEED or the fixed seed
+  --out DIR            default rust_sorts/bench
+  --per-element        divide by the size
+  --input FILES        time the sorts on .npy files instead of generated data, the file names
+                       are the distributions, --sizes takes prefixes, default the whole array
+  --dump DIR           save every generated input as DIR/<type>_<dist>_<size>.npy, the types
+                       numpy has: f32 f64 u32 i32 u64 i64";
+
+const TYPES: [&str; 11] = ["f32", "f64", "u32", "i32", "u64", "i64", "pair", "rec32", "rec64", "rec128", "string"];
+
+struct Config {
+    algos: Vec<Algorithm>,
+    dists: Vec<Dist>,
+    sizes: Vec<usize>,
+    events: Vec<Event>,
+    runs: usize,
+    warmup: usize,
+    seed: u64,
+    dump: Option<PathBuf>,
+}
+
+/// `npy::save` for the types numpy has
+type Save<T> = Option<fn(&Path, &[T]) -> io::Result<()>>;
+
+fn measure<T: Sortable + Clone>(config: &Config, algo: Algorithm, ty: &str, dist: &str, mut v: Vec<T>, rows: &mut Vec<Row>) {
+    let size = v.len();
+    let events = perf::events_for(algo, &config.events);
+    let measured = measure_sort(&mut v, config.warmup, config.runs, events, |v| algo.sort(v));
+    let time = &measured.time;
+    println!(
+        "{}/{}_{}/{}: median {:.3} μs, sd {:.3} μs, {} outliers",
+        algo,
+        ty,
+        dist,
+        size,
+        time.median() / 1e3,
+        time.sd() / 1e3,
+        time.outliers.len()
+    );
+    rows.extend(measured.to_rows(algo, size, ty, dist));
+}
+
+fn run<T: Sortable + Element + Clone>(config: &Config, ty: &str, save: Save<T>, rows: &mut Vec<Row>) {
+    for &dist in &config.dists {
+        if let Some(dir) = &config.dump {
+            match save {
+                Some(save) => {
+                    for &size in &config.sizes {
+                        let path = dir.join(format!("{}_{}_{}.npy", ty, dist, size));
+                        save(&path, &dist.generate::<T>(size, config.seed))
+                            .unwrap_or_else(|e| fail(&format!("writing {}: {}", path.display(), e)));
+                    }
+                }
+                None => eprintln!("{} has no numpy dtype, its {} inputs are not dumped", ty, dist),
+            }
+        }
+        // rows of a method in a row, like the result CSVs
+        for &algo in config.algos.iter().filter(|a| a.supports::<T>()) {
+            for &size in &config.sizes {
+                measure(config, algo, ty, dist.name(), dist.generate::<T>(size, config.seed), rows);
+            }
+        }
+    }
+}
+
+/// time the sorts on the flattened array of a `.npy` file, named after the file
+fn run_file<T: Sortable + LeBytes + Clone>(config: &Config, path: &Path, rows: &mut Vec<Row>) {
+    let data: Vec<T> = npy::load(path).unwrap_or_else(|e| fail(&format!("reading {}: {}", path.display(), e)));
+    let dist = path.file_stem().map_or("npy".into(), |s| s.to_string_lossy());
+    let sizes = if config.sizes.is_empty() { vec![data.len()] } else { config.sizes.clone() };
+    if let Some(size) = sizes.iter().find(|&&size| size > data.len()) {
+        fail(&format!("{} has {} elements, fewer than size {}", path.display(), data.len(), size));
+    }
+    for &algo in config.algos.iter().filter(|a| a.supports::<T>()) {
+        for &size in &sizes {
+            measure(config, algo, T::TYPE.name(), &dist, data[..size].to_vec(), rows);
+        }
+    }
+}
+
+macro_rules! run_types {
+    ($config: expr, $types: expr, $rows: expr, $($t: ty => $name: expr, $save: expr);*) => {
+        $(
+            if $types.iter().any(|t| t == $name) {
+                run::<$t>($config, $name, $save, $rows);
+            }
+        )*
+    };
+}
+
+fn list<T: FromStr>(s: &str) -> Vec<T>
+where
+    T::Err: std::fmt::Display,
+{
+    s.split(',')
+        .map(|x| x.trim().parse().unwrap_or_else(|e| fail(&format!("`{}`: {}", x, e))))
+        .collect()
+}
+
+fn main() {
+    let mut config = Config {
+        algos: Algorithm::ALL.to_vec(),
+        dists: vec![Dist::Uniform],
+        sizes: vec![1_000, 10_000, 100_000],
+        events: vec![Event::CpuCycle, Event::CacheMiss, Event::BranchMiss],
+        runs: 10,
+        warmup: 1,
+        seed: seed(),
+        dump: None,
+    };
+    let mut inputs: Vec<PathBuf> = vec![];
+    let mut sizes = false;
+    let mut types = vec!["f32".to_string()];
+    let mut out = PathBuf::from("rust_sorts/bench");
+    let mut per_element = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(&format!("`{}` needs a value\n{}", arg, USAGE)));
+        match arg.as_str() {
+            "--algos" => config.algos = list(&value()),
+            "--dists" => config.dists = list(&value()),
+            "--types" => types = list(&value()),
+            "--sizes" => {
+                config.sizes = list(&value());
+                sizes = true;
+            }
+            "--events" => {
+                let events = value();
+                config.events = if events == "none" { vec![] } else { list(&events) };
+            }
+            "--runs" => config.runs = value().parse().unwrap_or_else(|_| fail("--runs is not a number")),
+            "--warmup" => config.warmup = value().parse().unwrap_or_else(|_| fail("--warmup is not a number")),
+            "--seed" => config.seed = value().parse().unwrap_or_else(|_| fail("--seed is not a number")),
+            "--out" => out = value().into(),
+            "--per-element" => per_element = true,
+            "--input" => inputs = list(&value()),
+            "--dump" => config.dump = Some(value().into()),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => fail(&format!("unknown argument `{}`\n{}", arg, USAGE)),
+        }
+    }
+    if config.runs == 0 {
+        fail("--runs must be at least 1");
+    }
+    if let Some(ty) = types.iter().find(|t| !TYPES.contains(&t.as_str())) {
+        fail(&format!("unknown type `{}`", ty));
+    }
+    if let Some(dir) = &config.dump {
+        fs::create_dir_all(dir).unwrap_or_else(|e| fail(&format!("creating {}: {}", dir.display(), e)));
+    }
+
+    let mut rows = vec![];
+    if !inputs.is_empty() {
+        // the whole files unless `--sizes` asks for prefixes
+        if !sizes {
+            config.sizes.clear();
+        }
+        for path in &inputs {
+            let header = File::open(path)
+                .and_then(|f| npy::read_header(&mut BufReader::new(f)))
+                .unwrap_or_else(|e| fail(&format!("reading {}: {}", path.display(), e)));
+            match header.ty {
+                Type::F32 => run_file::<FloatOrd>(&config, path, &mut rows),
+                Type::F64 => run_file::<DoubleOrd>(&config, path, &mut rows),
+                Type::U32 => run_file::<u32>(&config, path, &mut rows),
+                Type::I32 => run_file::<i32>(&config, path, &mut rows),
+                Type::U64 => run_file::<u64>(&config, path, &mut rows),
+                Type::I64 => run_file::<i64>(&config, path, &mut rows),
+                Type::Auto | Type::Str => unreachable!("not a numpy dtype"),
+            }
+        }
+    } else {
+        println!("seed {}", config.seed);
+        run_types!(&config, types, &mut rows,
+            FloatOrd => "f32", Some(npy::save);
+            DoubleOrd => "f64", Some(npy::save);
+            u32 => "u32", Some(npy::save);
+            i32 => "i32", Some(npy::save);
+            u64 => "u64", Some(npy::save);
+            i64 => "i64", Some(npy::save);
+            (u64, u64) => "pair", None;
+            Record32 => "rec32", None;
+            Record64 => "rec64", None;
+            Record128 => "rec128", None;
+            String => "string", None
+        );
+    }
+    let counted = rows.iter().any(|r| r.measurement != Measurement::Time);
+    if !config.events.is_empty() && !counted {
+        eprintln!("perf can't count events here, only the time is written");
+    }
+    if per_element {
+        rows = rows.iter().map(|r| r.per_element()).collect();
+    }
+    export(&rows, &out).unwrap_or_else(|e| fail(&format!("writing {}: {}", out.display(), e)));
+    println!("wrote {} results to {}", rows.len(), out.display());
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(2)
+}
@@ -0,0 +1,46 @@
+//! Convert the criterion results of `cargo bench --bench bench` into the CSVs
+//! plotted by `rust_sorts/figs.py`
+//!
+//! ```shell
+//! cargo run --bin rusorts-export -- [--criterion-dir target/criterion] [--out rust_sorts/results] [--per-element]
+//! ```
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+use rust_sorts::export::{export, read_criterion_dir};
+
+const USAGE: &str = "usage: rusorts-export [--criterion-dir DIR] [--out DIR] [--per-element]";
+
+fn main() {
+    let mut criterion_dir = PathBuf::from("target/criterion");
+    let mut out = PathBuf::from("rust_sorts/results");
+    let mut per_element = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--criterion-dir" => criterion_dir = args.next().unwrap_or_else(|| fail(USAGE)).into(),
+            "--out" => out = args.next().unwrap_or_else(|| fail(USAGE)).into(),
+            "--per-element" => per_element = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => fail(&format!("unknown argument `{}`\n{}", arg, USAGE)),
+        }
+    }
+
+    let mut rows = read_criterion_dir(&criterion_dir)
+        .unwrap_or_else(|e| fail(&format!("reading {}: {}", criterion_dir.display(), e)));
+    if per_element {
+        rows = rows.iter().map(|r| r.per_element()).collect();
+    }
+    export(&rows, &out).unwrap_or_else(|e| fail(&format!("writing {}: {}", out.display(), e)));
+    println!("exported {} results to {}", rows.len(), out.display());
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(2)
+}
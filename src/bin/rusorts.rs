@@ -0,0 +1,196 @@
+//! Sort a file of numbers, strings, CSV rows or a binary array with any
+//! algorithm of the registry and write it back in the same format
+//!
+//! ```shell
+//! cargo run --release --bin rusorts -- --algo quad_pivot --reverse data.txt
+//! cargo run --release --bin rusorts -- --format csv --header --key-column age people.csv -o sorted.csv
+//! cargo run --release --bin rusorts -- --format binary --type f32 floats.bin -o sorted.bin
+//! cargo run --release --bin rusorts -- data.npy -o sorted.npy
+//! # 64 byte records with a u64 key at byte 8, sorted within 2 GiB of memory
+//! cargo run --release --bin rusorts -- --type u64 --record-size 64 --key-offset 8 --memory 2G events.bin -o sorted.bin
+//! ```
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+use rust_sorts::external::{self, sort_stream};
+use rust_sorts::format::{chain_text, sort_bytes, Format, Options, Type};
+use rust_sorts::npy;
+use rust_sorts::util::time_it;
+
+const USAGE: &str = "usage: rusorts [OPTIONS] [FILE]...
+
+sorts the concatenated FILEs, or stdin when there are none or FILE is -
+
+  --algo NAME          algorithm of the registry, default hoare_block
+  --format FORMAT      lines (default), csv, binary (little-endian array, default when sorted externally)
+                       or npy (numpy array of any shape sorted flat, default for .npy files)
+  --type TYPE          binary element type: i32 u32 i64 u64 f32 f64, .npy files have their own
+                       key type of text: auto (default) i64 u64 f64 str
+  --reverse            descending, equal keys keep their order
+  --unique             keep only the first of equal values or keys
+  --key-column COL     CSV column to sort by, index from 0 or header name, default 0
+  --delimiter CHAR     CSV delimiter, default ,
+  --header             the first CSV row is a header and stays first, the headers
+                       of any further CSV files are dropped
+  -o, --output FILE    write to FILE instead of stdout
+  --time               print the time of parsing and sorting to stderr
+
+binary input larger than memory, any of these sorts it externally:
+  --memory SIZE        budget like 512M or 2G, sorted runs are spilled and merged, default 1G
+                       (the merge reads at least 64K per run, up to 8M)
+  --record-size N      bytes per record, default the size of --type
+  --key-offset N       byte offset of the --type key in a record, default 0
+  --tmp-dir DIR        where the runs are spilled, default the system temp dir";
+
+fn main() {
+    let mut opts = Options::default();
+    let mut format = None;
+    let mut files = vec![];
+    let mut output = None;
+    let mut time = false;
+    let mut config = external::Config::default();
+    let mut external = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(&format!("`{}` needs a value\n{}", arg, USAGE)));
+        match arg.as_str() {
+            "--algo" => opts.algo = value().parse().unwrap_or_else(|e: String| fail(&e)),
+            "--format" => format = Some(value().parse().unwrap_or_else(|e: String| fail(&e))),
+            "--type" => opts.ty = value().parse().unwrap_or_else(|e: String| fail(&e)),
+            "--key-column" => opts.key_column = value(),
+            "--delimiter" => {
+                let delimiter = value();
+                let mut chars = delimiter.chars();
+                opts.delimiter = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => fail(&format!("delimiter `{}` is not one character", delimiter)),
+                };
+            }
+            "-o" | "--output" => output = Some(value()),
+            "--memory" => {
+                config.memory = parse_size(&value()).unwrap_or_else(|| fail("--memory is not a size like 512M"));
+                external = true;
+            }
+            "--record-size" => {
+                config.record_size = value().parse().unwrap_or_else(|_| fail("--record-size is not a number"));
+                external = true;
+            }
+            "--key-offset" => {
+                config.key_offset = value().parse().unwrap_or_else(|_| fail("--key-offset is not a number"));
+                external = true;
+            }
+            "--tmp-dir" => {
+                config.tmp_dir = value().into();
+                external = true;
+            }
+            "--reverse" => opts.reverse = true,
+            "--unique" => opts.unique = true,
+            "--header" => opts.header = true,
+            "--time" => time = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') && arg != "-" => fail(&format!("unknown argument `{}`\n{}", arg, USAGE)),
+            _ => files.push(arg),
+        }
+    }
+
+    // externally sorted input is always binary
+    let format = format.unwrap_or(if files.iter().any(|f| f.ends_with(".npy")) {
+        Format::Npy
+    } else if external {
+        Format::Binary
+    } else {
+        Format::Lines
+    });
+    if external && !matches!(format, Format::Binary | Format::Npy) {
+        fail("only binary and .npy input is sorted externally");
+    }
+    if format == Format::Npy && files.len() > 1 {
+        fail("sort one .npy file at a time");
+    }
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+    let inputs: Vec<Box<dyn Read>> = files
+        .iter()
+        .map(|file| -> Box<dyn Read> {
+            if file == "-" {
+                Box::new(io::stdin().lock())
+            } else {
+                Box::new(fs::File::open(file).unwrap_or_else(|e| fail(&format!("reading {}: {}", file, e))))
+            }
+        })
+        .collect();
+    // every CSV file has its own header, only the first one is kept
+    let mut input: Box<dyn Read> = match format {
+        Format::Lines | Format::Csv => chain_text(inputs, format == Format::Csv && opts.header),
+        _ => inputs.into_iter().fold(Box::new(io::empty()), |input, next| Box::new(input.chain(next))),
+    };
+    let output_name = output.as_deref().unwrap_or("stdout").to_string();
+    let mut out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(fs::File::create(path).unwrap_or_else(|e| fail(&format!("writing {}: {}", path, e)))),
+        None => Box::new(io::stdout().lock()),
+    };
+
+    if external {
+        if format == Format::Npy {
+            // the header is copied, the elements after it are sorted like a binary array
+            if opts.unique || config.record_size != 0 || config.key_offset != 0 {
+                fail("--unique, --record-size and --key-offset can't be used on .npy files sorted externally");
+            }
+            let header = npy::read_header(&mut input).unwrap_or_else(|e| fail(&format!("reading input: {}", e)));
+            if opts.ty != Type::Auto && opts.ty != header.ty {
+                fail(&format!("--type is {} but the .npy file holds {}", opts.ty, header.ty));
+            }
+            opts.ty = header.ty;
+            npy::write_header(&mut out, &header).unwrap_or_else(|e| fail(&format!("writing {}: {}", output_name, e)));
+        }
+        let mut stats = Ok(external::Stats::default());
+        let ns = time_it(|| stats = sort_stream(input, &mut out, &opts, &config));
+        let stats = stats.unwrap_or_else(|e| fail(&e.to_string()));
+        if time {
+            eprintln!(
+                "{} sorted {} records in {:.3} ms, {} runs spilled, {} extra merge passes",
+                opts.algo,
+                stats.records,
+                ns as f64 / 1e6,
+                stats.runs,
+                stats.passes
+            );
+        }
+        return;
+    }
+
+    let mut data = vec![];
+    input.read_to_end(&mut data).unwrap_or_else(|e| fail(&format!("reading input: {}", e)));
+    let mut sorted = None;
+    let ns = time_it(|| sorted = Some(sort_bytes(data, format, &opts)));
+    let sorted = sorted.unwrap().unwrap_or_else(|e| fail(&e));
+    if time {
+        eprintln!("{} sorted {} in {:.3} ms", opts.algo, format, ns as f64 / 1e6);
+    }
+    out.write_all(&sorted)
+        .and_then(|()| out.flush())
+        .unwrap_or_else(|e| fail(&format!("writing {}: {}", output_name, e)));
+}
+
+/// bytes, with an optional K, M or G suffix for KiB, MiB or GiB
+fn parse_size(s: &str) -> Option<usize> {
+    let (n, shift) = match s.char_indices().last()? {
+        (i, 'k' | 'K') => (&s[..i], 10),
+        (i, 'm' | 'M') => (&s[..i], 20),
+        (i, 'g' | 'G') => (&s[..i], 30),
+        _ => (s, 0),
+    };
+    n.parse::<usize>().ok()?.checked_mul(1 << shift)
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(2)
+}
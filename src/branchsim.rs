@@ -0,0 +1,188 @@
+use std::cell::RefCell;
+
+use crate::export::{Measurement, Row};
+use crate::probe::Traced;
+use crate::registry::Algorithm;
+
+/// A branch predictor model, `site` is the address of the branch
+pub trait Predictor {
+    fn predict(&mut self, site: u32) -> bool;
+    fn update(&mut self, site: u32, taken: bool);
+}
+
+/// 2-bit saturating counter, 0 and 1 predict not taken, 2 and 3 taken
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Counter(u8);
+
+impl Counter {
+    // weakly not taken
+    const INIT: Counter = Counter(1);
+
+    fn predict(self) -> bool {
+        self.0 >= 2
+    }
+
+    fn update(&mut self, taken: bool) {
+        self.0 = if taken { (self.0 + 1).min(3) } else { self.0.saturating_sub(1) };
+    }
+}
+
+/// One 2-bit saturating counter per branch, indexed by the low `bits` of the site
+#[derive(Debug, Clone)]
+pub struct TwoBit {
+    counters: Vec<Counter>,
+}
+
+impl TwoBit {
+    pub fn new(bits: u32) -> Self {
+        TwoBit { counters: vec![Counter::INIT; 1 << bits] }
+    }
+
+    fn index(&self, site: u32) -> usize {
+        site as usize & (self.counters.len() - 1)
+    }
+}
+
+impl Default for TwoBit {
+    fn default() -> Self {
+        TwoBit::new(12)
+    }
+}
+
+impl Predictor for TwoBit {
+    fn predict(&mut self, site: u32) -> bool {
+        self.counters[self.index(site)].predict()
+    }
+
+    fn update(&mut self, site: u32, taken: bool) {
+        let i = self.index(site);
+        self.counters[i].update(taken);
+    }
+}
+
+/// 2-bit counters indexed by the site xor the last `bits` branch outcomes
+#[derive(Debug, Clone)]
+pub struct Gshare {
+    counters: Vec<Counter>,
+    history: u32,
+}
+
+impl Gshare {
+    pub fn new(bits: u32) -> Self {
+        Gshare { counters: vec![Counter::INIT; 1 << bits], history: 0 }
+    }
+
+    fn index(&self, site: u32) -> usize {
+        (site ^ self.history) as usize & (self.counters.len() - 1)
+    }
+}
+
+impl Default for Gshare {
+    fn default() -> Self {
+        Gshare::new(12)
+    }
+}
+
+impl Predictor for Gshare {
+    fn predict(&mut self, site: u32) -> bool {
+        self.counters[self.index(site)].predict()
+    }
+
+    fn update(&mut self, site: u32, taken: bool) {
+        let i = self.index(site);
+        self.counters[i].update(taken);
+        self.history = (self.history << 1) | taken as u32;
+    }
+}
+
+/// Mispredictions of one simulated sort
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub elements: usize,
+    pub branches: u64,
+    pub mispredictions: u64,
+}
+
+impl Stats {
+    pub fn per_element(&self) -> f64 {
+        self.mispredictions as f64 / self.elements.max(1) as f64
+    }
+
+    /// mispredictions as a `branch_miss` row, the SD is always 0
+    pub fn to_row(&self, algo: Algorithm, ty: &str, dist: &str) -> Row {
+        let misses = self.mispredictions as f64;
+        Row {
+            method: algo.label().to_string(),
+            size: self.elements,
+            mean: misses,
+            sd: 0.0,
+            median: misses,
+            measurement: Measurement::BranchMiss,
+            ty: ty.to_string(),
+            dist: dist.to_string(),
+        }
+    }
+}
+
+struct Sim {
+    predictor: Box<dyn Predictor>,
+    stats: Stats,
+}
+
+thread_local! {
+    static SIM: RefCell<Option<Sim>> = const { RefCell::new(None) };
+}
+
+/// called by [`Traced`] for every branch that depends on a comparison
+pub(crate) fn branch(site: u32, taken: bool) {
+    SIM.with(|sim| {
+        if let Some(sim) = sim.borrow_mut().as_mut() {
+            sim.stats.branches += 1;
+            if sim.predictor.predict(site) != taken {
+                sim.stats.mispredictions += 1;
+            }
+            sim.predictor.update(site, taken);
+        }
+    })
+}
+
+/// removes the simulator even if the sort panics
+struct Uninstall;
+
+impl Drop for Uninstall {
+    fn drop(&mut self) {
+        SIM.with(|sim| sim.borrow_mut().take());
+    }
+}
+
+/// Sort `v` with `algo` and feed the outcome of every comparison dependent
+/// branch to `predictor`.
+///
+/// Loop bounds and the other branches that don't look at the elements are
+/// not simulated, a real CPU predicts them almost perfectly anyway.
+///
+/// The block partitions classify their blocks without branching, so their
+/// partitioning loops report nothing (`HoareBlock` only whether a block has
+/// anything to swap, `DualPivotNewBlock` its offset lookups), only the pivot
+/// selection and the insertion sort of small slices are reported like in the
+/// classical sorts.
+///
+/// # Panics
+///
+/// if `algo` can't be traced, see `Algorithm::supports::<Traced<T>>`, only
+/// the sequential sorts of `qsort.rs` go through the probes, the std,
+/// parallel and SIMD sorts don't
+pub fn simulate<T: Ord + Send>(algo: Algorithm, v: &mut [T], predictor: impl Predictor + 'static) -> Stats {
+    let sim = Sim {
+        predictor: Box::new(predictor),
+        stats: Stats { elements: v.len(), ..Stats::default() },
+    };
+    SIM.with(|s| {
+        let mut s = s.borrow_mut();
+        assert!(s.is_none(), "a branch simulation is already running on this thread");
+        *s = Some(sim);
+    });
+    let _uninstall = Uninstall;
+    algo.sort(Traced::slice(v));
+    SIM.with(|s| s.borrow().as_ref().map(|sim| sim.stats)).unwrap()
+}
@@ -0,0 +1,204 @@
+use std::ffi::CStr;
+
+use pyo3::buffer::{Element, ElementType, PyBuffer};
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::registry::Algorithm;
+use crate::ty::{DoubleOrd, FloatOrd};
+
+/// A writable, C contiguous buffer of numbers (numpy arrays, `array.array`,
+/// `memoryview`, `bytearray`, ...), sorted in place without copying.
+/// Floats are sorted as [`FloatOrd`](crate::ty::FloatOrd) / [`DoubleOrd`](crate::ty::DoubleOrd)
+pub(crate) enum NumBuffer {
+    I8(PyBuffer<Native<i8>>),
+    I16(PyBuffer<Native<i16>>),
+    I32(PyBuffer<Native<i32>>),
+    I64(PyBuffer<Native<i64>>),
+    U8(PyBuffer<Native<u8>>),
+    U16(PyBuffer<Native<u16>>),
+    U32(PyBuffer<Native<u32>>),
+    U64(PyBuffer<Native<u64>>),
+    F32(PyBuffer<Native<f32>>),
+    F64(PyBuffer<Native<f64>>),
+}
+
+pub(crate) const SUPPORTED: &str = "int8 to int64, uint8 to uint64, float32 or float64";
+
+/// Whether the byte order of a `struct` format string is the one of this
+/// machine, `<` is little-endian, `>` and `!` big-endian, `@`, `=` or no prefix native
+pub(crate) fn is_native_byte_order(format: &[u8]) -> bool {
+    match format.first() {
+        Some(b'<') if format.len() > 1 => cfg!(target_endian = "little"),
+        Some(b'>' | b'!') if format.len() > 1 => cfg!(target_endian = "big"),
+        _ => true,
+    }
+}
+
+/// Buffer element of native byte order. The check of pyo3 0.20 takes `>` for
+/// native on little-endian machines and rejects `<`, so it is done here
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub(crate) struct Native<T>(T);
+
+macro_rules! impl_native {
+    ($($t: ty => $kind: ident),*) => {
+        $(
+            unsafe impl Element for Native<$t> {
+                fn is_compatible_format(format: &CStr) -> bool {
+                    is_native_byte_order(format.to_bytes())
+                        && ElementType::from_format(format) == ElementType::$kind { bytes: std::mem::size_of::<$t>() }
+                }
+            }
+        )*
+    };
+}
+
+impl_native!(
+    i8 => SignedInteger, i16 => SignedInteger, i32 => SignedInteger, i64 => SignedInteger,
+    u8 => UnsignedInteger, u16 => UnsignedInteger, u32 => UnsignedInteger, u64 => UnsignedInteger,
+    f32 => Float, f64 => Float
+);
+
+fn check<T: Element>(buf: PyBuffer<T>) -> PyResult<PyBuffer<T>> {
+    if buf.readonly() {
+        Err(PyTypeError::new_err("buffer is read-only, it can't be sorted in place"))
+    } else if !buf.is_c_contiguous() {
+        Err(PyTypeError::new_err("buffer is not C contiguous, it can't be sorted in place"))
+    } else {
+        Ok(buf)
+    }
+}
+
+impl NumBuffer {
+    pub fn get(obj: &PyAny) -> PyResult<NumBuffer> {
+        macro_rules! try_get {
+            ($($variant: ident),*) => {
+                $(
+                    if let Ok(buf) = PyBuffer::get(obj) {
+                        return Ok(NumBuffer::$variant(check(buf)?));
+                    }
+                )*
+            };
+        }
+        try_get!(I8, I16, I32, I64, U8, U16, U32, U64, F32, F64);
+        // a buffer of a supported type, but swapped bytes
+        let memoryview = obj.py().import("builtins")?.getattr("memoryview")?;
+        if let Ok(format) = memoryview.call1((obj,)).and_then(|m| m.getattr("format")?.extract::<String>()) {
+            if !is_native_byte_order(format.as_bytes()) {
+                return Err(PyTypeError::new_err(format!(
+                    "buffer format `{}` is not in native byte order, it can't be sorted in place",
+                    format
+                )));
+            }
+        }
+        Err(PyTypeError::new_err(format!(
+            "expected a list or a buffer of {}, got {}",
+            SUPPORTED,
+            obj.get_type().name()?
+        )))
+    }
+}
+
+/// numpy names of the buffer element types `algo` can sort
+pub(crate) fn dtypes(algo: Algorithm) -> Vec<&'static str> {
+    macro_rules! supported {
+        ($($t: ty => $name: expr),*) => {
+            [$((algo.supports::<$t>(), $name)),*].into_iter().filter(|&(s, _)| s).map(|(_, name)| name).collect()
+        };
+    }
+    supported!(
+        i8 => "int8", i16 => "int16", i32 => "int32", i64 => "int64",
+        u8 => "uint8", u16 => "uint16", u32 => "uint32", u64 => "uint64",
+        FloatOrd => "float32", DoubleOrd => "float64"
+    )
+}
+
+/// `array.array` typecode of a numpy dtype name
+fn typecode(dtype: &str) -> Option<&'static str> {
+    Some(match dtype {
+        "int8" => "b",
+        "int16" => "h",
+        "int32" => "i",
+        "int64" => "q",
+        "uint8" => "B",
+        "uint16" => "H",
+        "uint32" => "I",
+        "uint64" => "Q",
+        "float32" => "f",
+        "float64" => "d",
+        _ => return None,
+    })
+}
+
+/// Copy `v` into a new `array.array`, numpy wraps it without copying
+///
+/// # Safety
+///
+/// `T` must be the type `with_dtype!` gives `dtype`
+pub(crate) unsafe fn new_array<'py, T>(py: Python<'py>, dtype: &str, v: &[T]) -> PyResult<&'py PyAny> {
+    let code = typecode(dtype).ok_or_else(|| PyTypeError::new_err(format!("unknown dtype `{}`", dtype)))?;
+    let bytes = std::slice::from_raw_parts(v.as_ptr() as *const u8, std::mem::size_of_val(v));
+    py.import("array")?.getattr("array")?.call1((code, PyBytes::new(py, bytes)))
+}
+
+/// Evaluate `$body` with the type `$t` of a numpy dtype name, the floats are
+/// `FloatOrd` and `DoubleOrd`
+macro_rules! with_dtype {
+    ($dtype: expr, |$t: ident| $body: expr) => {{
+        use $crate::ty::{DoubleOrd, FloatOrd};
+        match $dtype {
+            "int8" => { type $t = i8; $body }
+            "int16" => { type $t = i16; $body }
+            "int32" => { type $t = i32; $body }
+            "int64" => { type $t = i64; $body }
+            "uint8" => { type $t = u8; $body }
+            "uint16" => { type $t = u16; $body }
+            "uint32" => { type $t = u32; $body }
+            "uint64" => { type $t = u64; $body }
+            "float32" => { type $t = FloatOrd; $body }
+            "float64" => { type $t = DoubleOrd; $body }
+            other => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "unknown dtype `{}`, expected {}",
+                other,
+                $crate::buffer::SUPPORTED
+            ))),
+        }
+    }};
+}
+
+pub(crate) use with_dtype;
+
+/// # Safety
+///
+/// `T` must have the layout of `B`, the buffer must be checked by `NumBuffer::get`
+/// and nothing else may access it while the slice lives
+#[allow(clippy::mut_from_ref)]
+pub(crate) unsafe fn slice_of<B: Element, T>(buf: &PyBuffer<B>) -> &mut [T] {
+    std::slice::from_raw_parts_mut(buf.buf_ptr() as *mut T, buf.item_count())
+}
+
+/// Evaluate `$body` with `$v` bound to the buffer contents as a `&mut [T]`,
+/// `$body` must compile for every element type. Floats are FloatOrd and
+/// DoubleOrd, which are repr(transparent) over f32 and f64
+macro_rules! with_num_slice {
+    ($buf: expr, |$v: ident| $body: expr) => {{
+        use $crate::buffer::{slice_of, NumBuffer};
+        use $crate::ty::{DoubleOrd, FloatOrd};
+        match &$buf {
+            NumBuffer::I8(b) => { let $v = unsafe { slice_of::<_, i8>(b) }; $body }
+            NumBuffer::I16(b) => { let $v = unsafe { slice_of::<_, i16>(b) }; $body }
+            NumBuffer::I32(b) => { let $v = unsafe { slice_of::<_, i32>(b) }; $body }
+            NumBuffer::I64(b) => { let $v = unsafe { slice_of::<_, i64>(b) }; $body }
+            NumBuffer::U8(b) => { let $v = unsafe { slice_of::<_, u8>(b) }; $body }
+            NumBuffer::U16(b) => { let $v = unsafe { slice_of::<_, u16>(b) }; $body }
+            NumBuffer::U32(b) => { let $v = unsafe { slice_of::<_, u32>(b) }; $body }
+            NumBuffer::U64(b) => { let $v = unsafe { slice_of::<_, u64>(b) }; $body }
+            NumBuffer::F32(b) => { let $v = unsafe { slice_of::<_, FloatOrd>(b) }; $body }
+            NumBuffer::F64(b) => { let $v = unsafe { slice_of::<_, DoubleOrd>(b) }; $body }
+        }
+    }};
+}
+
+pub(crate) use with_num_slice;
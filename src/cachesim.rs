@@ -0,0 +1,221 @@
+use std::cell::RefCell;
+
+use crate::export::{Measurement, Row};
+use crate::probe::Traced;
+use crate::registry::Algorithm;
+
+/// One set-associative cache level with LRU replacement, sizes in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    pub size: usize,
+    pub ways: usize,
+    pub line: usize,
+}
+
+impl Level {
+    pub const fn new(size: usize, ways: usize, line: usize) -> Self {
+        Level { size, ways, line }
+    }
+
+    pub fn sets(&self) -> usize {
+        self.size / (self.ways * self.line)
+    }
+}
+
+/// The simulated hierarchy, every level allocates on loads and stores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub l1: Level,
+    pub l2: Level,
+    pub llc: Level,
+}
+
+impl Default for Config {
+    /// a typical desktop core: 32 KiB 8-way L1d, 256 KiB 4-way L2 and 8 MiB 16-way LLC
+    fn default() -> Self {
+        Config {
+            l1: Level::new(32 << 10, 8, 64),
+            l2: Level::new(256 << 10, 4, 64),
+            llc: Level::new(8 << 20, 16, 64),
+        }
+    }
+}
+
+/// Misses of one simulated sort
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub elements: usize,
+    /// element loads and stores, an access may touch more than one line
+    pub accesses: u64,
+    pub l1_misses: u64,
+    pub l2_misses: u64,
+    pub llc_misses: u64,
+}
+
+impl Stats {
+    fn per_element(&self, misses: u64) -> f64 {
+        misses as f64 / self.elements.max(1) as f64
+    }
+
+    pub fn l1_per_element(&self) -> f64 {
+        self.per_element(self.l1_misses)
+    }
+
+    pub fn l2_per_element(&self) -> f64 {
+        self.per_element(self.l2_misses)
+    }
+
+    pub fn llc_per_element(&self) -> f64 {
+        self.per_element(self.llc_misses)
+    }
+
+    /// LLC misses as a `cache_miss` row, the closest to what perf counts.
+    /// The simulation is deterministic, so the SD is always 0
+    pub fn to_row(&self, algo: Algorithm, ty: &str, dist: &str) -> Row {
+        let misses = self.llc_misses as f64;
+        Row {
+            method: algo.label().to_string(),
+            size: self.elements,
+            mean: misses,
+            sd: 0.0,
+            median: misses,
+            measurement: Measurement::CacheMiss,
+            ty: ty.to_string(),
+            dist: dist.to_string(),
+        }
+    }
+}
+
+struct Cache {
+    level: Level,
+    sets: u64,
+    /// `ways` line numbers per set, most recently used first
+    lines: Vec<u64>,
+}
+
+impl Cache {
+    fn new(level: Level) -> Self {
+        assert!(level.sets() > 0, "{:?} has no sets", level);
+        Cache {
+            level,
+            sets: level.sets() as u64,
+            lines: vec![u64::MAX; level.sets() * level.ways],
+        }
+    }
+
+    /// true on a hit, the line becomes the most recently used of its set either way
+    fn access(&mut self, addr: u64) -> bool {
+        let line = addr / self.level.line as u64;
+        let set = (line % self.sets) as usize;
+        let ways = &mut self.lines[set * self.level.ways..(set + 1) * self.level.ways];
+        match ways.iter().position(|&l| l == line) {
+            Some(i) => {
+                ways[..=i].rotate_right(1);
+                true
+            }
+            None => {
+                ways.rotate_right(1);
+                ways[0] = line;
+                false
+            }
+        }
+    }
+}
+
+// the slice and the stack are mapped to fixed addresses, so that neither the
+// allocator nor ASLR changes which sets they fall into
+const DATA_BASE: u64 = 1 << 40;
+const STACK_BASE: u64 = 1 << 48;
+
+struct Sim {
+    l1: Cache,
+    l2: Cache,
+    llc: Cache,
+    stats: Stats,
+    data: (usize, usize),
+    stack: usize,
+}
+
+impl Sim {
+    fn translate(&self, addr: usize) -> u64 {
+        let (lo, hi) = self.data;
+        if (lo..hi).contains(&addr) {
+            DATA_BASE + (addr - lo) as u64
+        } else {
+            STACK_BASE.wrapping_add(addr.wrapping_sub(self.stack) as u64)
+        }
+    }
+
+    fn access(&mut self, addr: usize, size: usize) {
+        self.stats.accesses += 1;
+        let start = self.translate(addr);
+        let line = self.l1.level.line as u64;
+        let mut addr = start / line * line;
+        while addr < start + size.max(1) as u64 {
+            if !self.l1.access(addr) {
+                self.stats.l1_misses += 1;
+                if !self.l2.access(addr) {
+                    self.stats.l2_misses += 1;
+                    if !self.llc.access(addr) {
+                        self.stats.llc_misses += 1;
+                    }
+                }
+            }
+            addr += line;
+        }
+    }
+}
+
+thread_local! {
+    static SIM: RefCell<Option<Sim>> = const { RefCell::new(None) };
+}
+
+/// called by [`Traced`] for every load and store
+pub(crate) fn access(addr: usize, size: usize) {
+    SIM.with(|sim| {
+        if let Some(sim) = sim.borrow_mut().as_mut() {
+            sim.access(addr, size);
+        }
+    })
+}
+
+/// removes the simulator even if the sort panics
+struct Uninstall;
+
+impl Drop for Uninstall {
+    fn drop(&mut self) {
+        SIM.with(|sim| sim.borrow_mut().take());
+    }
+}
+
+/// Sort `v` with `algo` and replay every element load and store through a
+/// cold cache hierarchy.
+///
+/// The slice is mapped to a line aligned address and stack accesses (the
+/// pivot copies) are relative to this call, so the result only depends on
+/// the algorithm, the data and the config. Heap memory owned by the elements
+/// (the bytes of a `String`) is not traced.
+///
+/// # Panics
+///
+/// if `algo` can't be traced, see `Algorithm::supports::<Traced<T>>`
+pub fn simulate<T: Ord + Send>(algo: Algorithm, v: &mut [T], config: &Config) -> Stats {
+    let anchor = 0u8;
+    let data = v.as_ptr_range();
+    let sim = Sim {
+        l1: Cache::new(config.l1),
+        l2: Cache::new(config.l2),
+        llc: Cache::new(config.llc),
+        stats: Stats { elements: v.len(), ..Stats::default() },
+        data: (data.start as usize, data.end as usize),
+        stack: &anchor as *const u8 as usize,
+    };
+    SIM.with(|s| {
+        let mut s = s.borrow_mut();
+        assert!(s.is_none(), "a cache simulation is already running on this thread");
+        *s = Some(sim);
+    });
+    let _uninstall = Uninstall;
+    algo.sort(Traced::slice(v));
+    SIM.with(|s| s.borrow().as_ref().map(|sim| sim.stats)).unwrap()
+}
@@ -0,0 +1,170 @@
+use core::cmp::Ordering;
+use std::cell::RefCell;
+
+use crate::export::{Measurement, Row};
+use crate::probe::{Probe, Traced};
+use crate::registry::{self, Algorithm, Sortable};
+
+/// Operations of one sort call
+///
+/// - `swaps` counts exchanges of two elements
+/// - `moves` counts element writes, including the two of every swap and one per rotated element
+/// - `scanned` counts slice elements compared other than the one compared just
+///   before, so comparing the same element against several pivots scans it
+///   once, like the scanned elements of Kushagra et al. Every sort compares
+///   against pivots copied out of the slice, and the loads of swaps and moves
+///   don't count, so the numbers compare across algorithms
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub elements: usize,
+    pub comparisons: u64,
+    pub swaps: u64,
+    pub moves: u64,
+    pub scanned: u64,
+}
+
+impl Counts {
+    /// one row per operation, the counts are deterministic so the SD is always 0
+    pub fn to_rows(&self, algo: Algorithm, ty: &str, dist: &str) -> Vec<Row> {
+        [
+            (Measurement::Comparison, self.comparisons),
+            (Measurement::Swap, self.swaps),
+            (Measurement::Move, self.moves),
+            (Measurement::ScannedElement, self.scanned),
+        ]
+        .into_iter()
+        .map(|(measurement, count)| Row {
+            method: algo.label().to_string(),
+            size: self.elements,
+            mean: count as f64,
+            sd: 0.0,
+            median: count as f64,
+            measurement,
+            ty: ty.to_string(),
+            dist: dist.to_string(),
+        })
+        .collect()
+    }
+}
+
+struct Counter {
+    counts: Counts,
+    data: (usize, usize),
+    last: usize,
+}
+
+thread_local! {
+    static COUNTER: RefCell<Option<Counter>> = const { RefCell::new(None) };
+}
+
+fn with_counter(f: impl FnOnce(&mut Counter)) {
+    COUNTER.with(|c| {
+        if let Some(c) = c.borrow_mut().as_mut() {
+            f(c);
+        }
+    })
+}
+
+/// Element that counts the operations of the sorts in `qsort.rs`
+#[repr(transparent)]
+#[derive(Debug, Clone, Default)]
+pub struct Counted<T>(pub T);
+
+impl<T> Counted<T> {
+    /// view a slice as counted elements without copying it
+    pub fn slice(v: &mut [T]) -> &mut [Counted<T>] {
+        // Counted is repr(transparent)
+        unsafe { &mut *(v as *mut [T] as *mut [Counted<T>]) }
+    }
+}
+
+/// an element read by a comparison, pivots and other copies are outside the slice
+#[inline]
+fn scan<T>(p: *const T) {
+    let p = p as usize;
+    with_counter(|c| {
+        if (c.data.0..c.data.1).contains(&p) {
+            c.counts.scanned += (p != c.last) as u64;
+            c.last = p;
+        }
+    })
+}
+
+impl<T> Probe for Counted<T> {
+    #[inline]
+    fn store(_p: *const Self) {
+        with_counter(|c| c.counts.moves += 1)
+    }
+
+    #[inline]
+    fn swapped() {
+        with_counter(|c| c.counts.swaps += 1)
+    }
+}
+
+impl<T: Ord> PartialEq for Counted<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Counted<T> {}
+
+impl<T: Ord> PartialOrd for Counted<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Counted<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        with_counter(|c| c.counts.comparisons += 1);
+        scan(self);
+        scan(other);
+        self.0.cmp(&other.0)
+    }
+}
+
+// the same sorts as `Traced` go through the probes
+impl<T: Ord + Send> Sortable for Counted<T> {
+    fn supports(algo: Algorithm) -> bool {
+        Traced::<T>::supports(algo)
+    }
+
+    fn sort_with(algo: Algorithm, v: &mut [Self]) {
+        assert!(Self::supports(algo), "{} can not be counted", algo);
+        registry::sort_generic(algo, v)
+    }
+}
+
+/// removes the counter even if the sort panics
+struct Uninstall;
+
+impl Drop for Uninstall {
+    fn drop(&mut self) {
+        COUNTER.with(|c| c.borrow_mut().take());
+    }
+}
+
+/// Sort `v` with `algo` and count its operations
+///
+/// # Panics
+///
+/// if `algo` can't be counted, see `Algorithm::supports::<Counted<T>>`
+pub fn count<T: Ord + Send>(algo: Algorithm, v: &mut [T]) -> Counts {
+    let data = v.as_ptr_range();
+    let counter = Counter {
+        counts: Counts { elements: v.len(), ..Counts::default() },
+        data: (data.start as usize, data.end as usize),
+        last: usize::MAX,
+    };
+    COUNTER.with(|c| {
+        let mut c = c.borrow_mut();
+        assert!(c.is_none(), "operations are already counted on this thread");
+        *c = Some(counter);
+    });
+    let _uninstall = Uninstall;
+    algo.sort(Counted::slice(v));
+    COUNTER.with(|c| c.borrow().as_ref().map(|c| c.counts)).unwrap()
+}
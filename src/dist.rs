@@ -0,0 +1,293 @@
+use core::fmt;
+use core::str::FromStr;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::ty::{DoubleOrd, FloatOrd, Record};
+
+/// Number of distinct values produced by [`few_unique`]
+pub const FEW_UNIQUE: u64 = 16;
+
+/// Element types the generators can produce.
+///
+/// Every generator works on `u64` keys, `from_key` maps them onto the element
+/// type while preserving their order, so the same seed gives the same shape
+/// of data for every type
+pub trait Element: Sized {
+    fn from_key(key: u64) -> Self;
+}
+
+macro_rules! impl_element_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl Element for $t {
+                #[inline]
+                fn from_key(key: u64) -> Self {
+                    (key >> (64 - <$t>::BITS)) as $t
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_element_signed {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl Element for $t {
+                #[inline]
+                fn from_key(key: u64) -> Self {
+                    // flip the sign bit so that key 0 maps to MIN and u64::MAX to MAX
+                    (<$u>::from_key(key) as $t) ^ <$t>::MIN
+                }
+            }
+        )*
+    };
+}
+
+impl_element_unsigned!(u8, u16, u32, u64, usize);
+impl_element_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize);
+
+impl Element for f32 {
+    #[inline]
+    fn from_key(key: u64) -> Self {
+        // same [0, 1) range as sampling `Standard`
+        (key >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+impl Element for f64 {
+    #[inline]
+    fn from_key(key: u64) -> Self {
+        (key >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl Element for FloatOrd {
+    #[inline]
+    fn from_key(key: u64) -> Self {
+        FloatOrd(f32::from_key(key))
+    }
+}
+
+impl Element for DoubleOrd {
+    #[inline]
+    fn from_key(key: u64) -> Self {
+        DoubleOrd(f64::from_key(key))
+    }
+}
+
+impl Element for (u64, u64) {
+    #[inline]
+    fn from_key(key: u64) -> Self {
+        (key >> 32, key & 0xffff_ffff)
+    }
+}
+
+impl<const PAD: usize> Element for Record<PAD> {
+    #[inline]
+    fn from_key(key: u64) -> Self {
+        Record::new(key)
+    }
+}
+
+impl Element for String {
+    /// fixed width hex, so the strings compare like the keys
+    fn from_key(key: u64) -> Self {
+        format!("{:016x}", key)
+    }
+}
+
+#[inline]
+fn rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// spread `rank` out of `n` ranks evenly over the whole key space
+#[inline]
+fn spread(rank: u64, n: u64) -> u64 {
+    rank * (u64::MAX / n.max(1))
+}
+
+fn from_ranks<T: Element>(n: usize, ranks: impl Iterator<Item = u64>) -> Vec<T> {
+    ranks.map(|r| T::from_key(spread(r, n as u64))).collect()
+}
+
+/// swap about sqrt(n) random pairs
+fn shuffle_sqrt<T>(v: &mut [T], rng: &mut StdRng) {
+    let len = v.len();
+    if len == 0 {
+        return;
+    }
+    for _ in (0usize..).take_while(|x| x * x <= len) {
+        let x = rng.gen::<usize>() % len;
+        let y = rng.gen::<usize>() % len;
+        v.swap(x, y);
+    }
+}
+
+pub fn uniform<T: Element>(n: usize, seed: u64) -> Vec<T> {
+    let mut rng = rng(seed);
+    (0..n).map(|_| T::from_key(rng.gen())).collect()
+}
+
+pub fn sorted<T: Element>(n: usize, _seed: u64) -> Vec<T> {
+    from_ranks(n, 0..n as u64)
+}
+
+pub fn reversed<T: Element>(n: usize, _seed: u64) -> Vec<T> {
+    from_ranks(n, (0..n as u64).rev())
+}
+
+/// ascending up to the middle, then descending
+pub fn organ_pipe<T: Element>(n: usize, _seed: u64) -> Vec<T> {
+    let n = n as u64;
+    let half = n.div_ceil(2);
+    (0..n).map(|i| T::from_key(spread(i.min(n - 1 - i), half))).collect()
+}
+
+/// ascending runs of about sqrt(n) elements each
+pub fn sawtooth<T: Element>(n: usize, _seed: u64) -> Vec<T> {
+    let run = ((n as f64).sqrt() as u64).max(1);
+    (0..n as u64).map(|i| T::from_key(spread(i % run, run))).collect()
+}
+
+/// only [`FEW_UNIQUE`] distinct values in random order
+pub fn few_unique<T: Element>(n: usize, seed: u64) -> Vec<T> {
+    let mut rng = rng(seed);
+    (0..n)
+        .map(|_| T::from_key(spread(rng.gen_range(0..FEW_UNIQUE), FEW_UNIQUE)))
+        .collect()
+}
+
+pub fn all_equal<T: Element>(n: usize, _seed: u64) -> Vec<T> {
+    (0..n).map(|_| T::from_key(u64::MAX / 2)).collect()
+}
+
+/// Zipf distribution with exponent 1 over n ranks, small ranks are the most frequent
+pub fn zipf<T: Element>(n: usize, seed: u64) -> Vec<T> {
+    let mut rng = rng(seed);
+    let ranks = n as f64 + 1.0;
+    // inverse of the continuous cdf ln(x + 1) / ln(n + 1)
+    (0..n)
+        .map(|_| {
+            let rank = (ranks.powf(rng.gen::<f64>()) - 1.0) as u64;
+            T::from_key(spread(rank.min(n as u64 - 1), n as u64))
+        })
+        .collect()
+}
+
+/// normal distribution centred in the key space, clamped to 6 standard deviations
+pub fn gaussian<T: Element>(n: usize, seed: u64) -> Vec<T> {
+    const SIGMAS: f64 = 6.0;
+    let mut rng = rng(seed);
+    (0..n)
+        .map(|_| {
+            // Box-Muller transform, 1 - u keeps the log argument in (0, 1]
+            let (u1, u2) = (1.0 - rng.gen::<f64>(), rng.gen::<f64>());
+            let z = (-2.0 * u1.ln()).sqrt() * (2.0 * core::f64::consts::PI * u2).cos();
+            let z = z.clamp(-SIGMAS, SIGMAS);
+            T::from_key(((z + SIGMAS) / (2.0 * SIGMAS) * u64::MAX as f64) as u64)
+        })
+        .collect()
+}
+
+/// sorted, except the largest element is pushed to the front
+pub fn pushed_front<T: Element>(n: usize, seed: u64) -> Vec<T> {
+    let mut v = sorted(n, seed);
+    if !v.is_empty() {
+        v.rotate_right(1);
+    }
+    v
+}
+
+/// sorted, except the smallest element is pushed to the back
+pub fn pushed_back<T: Element>(n: usize, seed: u64) -> Vec<T> {
+    let mut v = sorted(n, seed);
+    if !v.is_empty() {
+        v.rotate_left(1);
+    }
+    v
+}
+
+/// sorted with about sqrt(n) random swaps
+pub fn mostly_ascending<T: Element>(n: usize, seed: u64) -> Vec<T> {
+    let mut v = sorted(n, seed);
+    shuffle_sqrt(&mut v, &mut rng(seed));
+    v
+}
+
+/// reversed with about sqrt(n) random swaps
+pub fn mostly_descending<T: Element>(n: usize, seed: u64) -> Vec<T> {
+    let mut v = reversed(n, seed);
+    shuffle_sqrt(&mut v, &mut rng(seed));
+    v
+}
+
+/// random mix of the smallest and the largest value
+pub fn random_01<T: Element>(n: usize, seed: u64) -> Vec<T> {
+    let mut rng = rng(seed);
+    (0..n)
+        .map(|_| T::from_key(if rng.gen::<bool>() { u64::MAX } else { 0 }))
+        .collect()
+}
+
+macro_rules! impl_dist {
+    ($($variant:ident => $func:ident),* $(,)?) => {
+        /// Input distributions, see the generator functions of the same name
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Dist {
+            $($variant,)*
+        }
+
+        impl Dist {
+            pub const ALL: &'static [Dist] = &[$(Dist::$variant,)*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Dist::$variant => stringify!($func),)*
+                }
+            }
+
+            pub fn generate<T: Element>(self, n: usize, seed: u64) -> Vec<T> {
+                match self {
+                    $(Dist::$variant => $func(n, seed),)*
+                }
+            }
+        }
+    };
+}
+
+impl_dist!(
+    Uniform => uniform,
+    Sorted => sorted,
+    Reversed => reversed,
+    OrganPipe => organ_pipe,
+    Sawtooth => sawtooth,
+    FewUnique => few_unique,
+    AllEqual => all_equal,
+    Zipf => zipf,
+    Gaussian => gaussian,
+    PushedFront => pushed_front,
+    PushedBack => pushed_back,
+    MostlyAscending => mostly_ascending,
+    MostlyDescending => mostly_descending,
+    Random01 => random_01,
+);
+
+impl fmt::Display for Dist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Dist {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Dist::ALL
+            .iter()
+            .copied()
+            .find(|d| d.name() == s)
+            .ok_or_else(|| format!("unknown distribution `{}`", s))
+    }
+}
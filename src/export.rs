@@ -0,0 +1,252 @@
+use core::fmt;
+use core::str::FromStr;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use rustc_serialize::json::{Json, ToJson};
+
+use crate::registry::Algorithm;
+
+/// What a benchmark group measured, the group names end with `_<suffix>`.
+/// The operation counts come from `counted::count` instead of criterion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Measurement {
+    Time,
+    CpuCycle,
+    CacheMiss,
+    BranchMiss,
+    Comparison,
+    Swap,
+    Move,
+    ScannedElement,
+}
+
+impl Measurement {
+    pub const ALL: &'static [Measurement] = &[
+        Measurement::Time,
+        Measurement::CpuCycle,
+        Measurement::CacheMiss,
+        Measurement::BranchMiss,
+        Measurement::Comparison,
+        Measurement::Swap,
+        Measurement::Move,
+        Measurement::ScannedElement,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Measurement::Time => "time",
+            Measurement::CpuCycle => "cpu_cycle",
+            Measurement::CacheMiss => "cache_miss",
+            Measurement::BranchMiss => "branch_miss",
+            Measurement::Comparison => "comparison",
+            Measurement::Swap => "swap",
+            Measurement::Move => "move",
+            Measurement::ScannedElement => "scanned_element",
+        }
+    }
+
+    /// unit of the exported values, criterion reports time in ns but the CSVs use μs
+    pub fn unit(self) -> &'static str {
+        match self {
+            Measurement::Time => "μs",
+            Measurement::CpuCycle => "cycles",
+            Measurement::CacheMiss => "cache misses",
+            Measurement::BranchMiss => "branch misses",
+            Measurement::Comparison => "comparisons",
+            Measurement::Swap => "swaps",
+            Measurement::Move => "moves",
+            Measurement::ScannedElement => "scanned elements",
+        }
+    }
+
+    fn scale(self) -> f64 {
+        match self {
+            Measurement::Time => 1e-3,
+            _ => 1.0,
+        }
+    }
+
+    /// split a group name like `hoare_block_time` into `("hoare_block", Time)`
+    pub fn split_group(group: &str) -> Option<(&str, Measurement)> {
+        Measurement::ALL.iter().find_map(|&m| {
+            group
+                .strip_suffix(m.name())
+                .and_then(|g| g.strip_suffix('_'))
+                .map(|g| (g, m))
+        })
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Measurement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Measurement::ALL
+            .iter()
+            .copied()
+            .find(|m| m.name() == s)
+            .ok_or_else(|| format!("unknown measurement `{}`", s))
+    }
+}
+
+/// One row of the result CSVs read by `rust_sorts/figs.py`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub method: String,
+    pub size: usize,
+    pub mean: f64,
+    pub sd: f64,
+    pub median: f64,
+    pub measurement: Measurement,
+    pub ty: String,
+    pub dist: String,
+}
+
+impl Row {
+    /// mean, sd and median divided by the size
+    pub fn per_element(&self) -> Row {
+        let n = self.size.max(1) as f64;
+        Row {
+            mean: self.mean / n,
+            sd: self.sd / n,
+            median: self.median / n,
+            ..self.clone()
+        }
+    }
+}
+
+impl ToJson for Row {
+    fn to_json(&self) -> Json {
+        let mut o = BTreeMap::new();
+        o.insert("Method".to_string(), self.method.to_json());
+        o.insert("Size".to_string(), self.size.to_json());
+        o.insert("Mean".to_string(), self.mean.to_json());
+        o.insert("SD".to_string(), self.sd.to_json());
+        o.insert("Median".to_string(), self.median.to_json());
+        o.insert("Measurement".to_string(), self.measurement.name().to_json());
+        o.insert("Unit".to_string(), self.measurement.unit().to_json());
+        o.insert("Type".to_string(), self.ty.to_json());
+        o.insert("Dist".to_string(), self.dist.to_json());
+        Json::Object(o)
+    }
+}
+
+fn invalid(path: &Path, what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), what))
+}
+
+fn read_json(path: &Path) -> io::Result<Json> {
+    Json::from_str(&fs::read_to_string(path)?).map_err(|e| invalid(path, &e.to_string()))
+}
+
+/// Parse one `<group>/<type>_<dist>/<size>/new` directory written by `benches/bench.rs`
+fn read_benchmark(dir: &Path) -> io::Result<Option<Row>> {
+    let benchmark = read_json(&dir.join("benchmark.json"))?;
+    let estimates = read_json(&dir.join("estimates.json"))?;
+    let field = |name: &str| {
+        benchmark
+            .find(name)
+            .and_then(Json::as_string)
+            .ok_or_else(|| invalid(dir, &format!("missing `{}`", name)))
+    };
+    let estimate = |name: &str| {
+        estimates
+            .find_path(&[name, "point_estimate"])
+            .and_then(Json::as_f64)
+            .ok_or_else(|| invalid(dir, &format!("missing `{}.point_estimate`", name)))
+    };
+
+    // only groups named `<algo>_<measurement>` come from the registry bench
+    let Some((algo, measurement)) = Measurement::split_group(field("group_id")?) else {
+        return Ok(None);
+    };
+    let method = algo.parse::<Algorithm>().map(|a| a.label().to_string()).unwrap_or_else(|_| algo.to_string());
+    let (ty, dist) = field("function_id")?.split_once('_').unwrap_or(("", ""));
+    let size = field("value_str")?.parse().map_err(|_| invalid(dir, "size is not an integer"))?;
+    let scale = measurement.scale();
+
+    Ok(Some(Row {
+        method,
+        size,
+        mean: estimate("mean")? * scale,
+        sd: estimate("std_dev")? * scale,
+        median: estimate("median")? * scale,
+        measurement,
+        ty: ty.to_string(),
+        dist: dist.to_string(),
+    }))
+}
+
+fn visit(dir: &Path, rows: &mut Vec<Row>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if path.file_name().is_some_and(|n| n == "new") && path.join("estimates.json").is_file() {
+            rows.extend(read_benchmark(&path)?);
+        } else {
+            visit(&path, rows)?;
+        }
+    }
+    Ok(())
+}
+
+/// Collect the latest results under `target/criterion`, sorted like the CSVs
+pub fn read_criterion_dir(dir: &Path) -> io::Result<Vec<Row>> {
+    let mut rows = vec![];
+    visit(dir, &mut rows)?;
+    rows.sort_by(|a, b| {
+        (a.measurement, &a.ty, &a.dist, &a.method, a.size).cmp(&(b.measurement, &b.ty, &b.dist, &b.method, b.size))
+    });
+    Ok(rows)
+}
+
+/// `field` in double quotes if it holds a comma, a quote or a line break
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// The layout of `rust_sorts/*_converted.csv`, bare numbers in the unit of the
+/// measurement (μs for time) without the `// ` rows and unit strings of the
+/// hand made `classical_time.csv`
+pub fn write_csv<W: Write>(mut w: W, rows: &[Row]) -> io::Result<()> {
+    writeln!(w, "Method,Size,Mean,SD,Median")?;
+    for r in rows {
+        writeln!(w, "{},{},{},{},{}", csv_field(&r.method), r.size, r.mean, r.sd, r.median)?;
+    }
+    Ok(())
+}
+
+pub fn write_json<W: Write>(mut w: W, rows: &[Row]) -> io::Result<()> {
+    writeln!(w, "{}", rows.to_json().pretty())
+}
+
+/// Write `<measurement>_<type>_<dist>.csv` per result set into `out` in the
+/// layout of [`write_csv`], plus every row into `results.json`
+pub fn export(rows: &[Row], out: &Path) -> io::Result<()> {
+    fs::create_dir_all(out)?;
+    let mut sets: BTreeMap<_, Vec<Row>> = BTreeMap::new();
+    for r in rows {
+        sets.entry((r.measurement, r.ty.clone(), r.dist.clone())).or_default().push(r.clone());
+    }
+    for ((measurement, ty, dist), rows) in &sets {
+        let path = out.join(format!("{}_{}_{}.csv", measurement, ty, dist));
+        write_csv(io::BufWriter::new(fs::File::create(path)?), rows)?;
+    }
+    write_json(io::BufWriter::new(fs::File::create(out.join("results.json"))?), rows)
+}
@@ -0,0 +1,267 @@
+use core::cmp::Reverse;
+use core::marker::PhantomData;
+use core::mem;
+use std::collections::BinaryHeap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+
+use crate::format::{sort_array, with_binary_type, LeBytes, Options};
+use crate::registry::Sortable;
+use crate::ty::Keyed;
+
+/// Runs merged at once, more runs are merged in several passes
+const FAN_IN: usize = 128;
+
+/// Smallest read buffer of a run while merging, even if the memory budget is
+/// smaller, so a merge may take up to `FAN_IN * MIN_BUFFER` (8 MiB)
+const MIN_BUFFER: usize = 64 * 1024;
+
+/// Memory and layout of an external sort, the element type and the order are
+/// the `--type`, `--algo`, `--reverse` and `--unique` of [`Options`]
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// bytes for the records being sorted at once, the read buffers of the
+    /// merge share the same budget but get at least 64 KiB per run, up to
+    /// 8 MiB for the 128 runs merged at once
+    pub memory: usize,
+    /// bytes per record, 0 for records that are just the key
+    pub record_size: usize,
+    /// byte offset of the little-endian key in a record
+    pub key_offset: usize,
+    /// where the sorted runs are spilled
+    pub tmp_dir: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config { memory: 1 << 30, record_size: 0, key_offset: 0, tmp_dir: std::env::temp_dir() }
+    }
+}
+
+/// What an external sort did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// records written, fewer than read with `unique`
+    pub records: u64,
+    /// sorted runs spilled to `tmp_dir`, 0 if the input fit in memory
+    pub runs: usize,
+    /// merge passes before the last one, when there were more than `FAN_IN` runs
+    pub passes: usize,
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Sort the fixed-width records of `input` by their key into `output`,
+/// reading at most `config.memory` bytes of it at a time. Every chunk is
+/// split into one piece per rayon thread, the pieces are sorted in parallel
+/// and merged into a run on disk, then the runs are k-way merged. Equal keys
+/// keep their input order
+pub fn sort_stream(input: impl Read, output: impl Write, opts: &Options, config: &Config) -> io::Result<Stats> {
+    with_binary_type!(opts.ty, |T| sort_records::<T>(input, output, opts, config), {
+        Err(invalid(format!("external sort needs a binary `--type`, not {}", opts.ty)))
+    })
+}
+
+/// [`sort_stream`] with keys of type `T`
+pub fn sort_records<T>(input: impl Read, output: impl Write, opts: &Options, config: &Config) -> io::Result<Stats>
+where
+    T: LeBytes + Sortable + Copy,
+    Keyed<T>: Sortable,
+    Keyed<Reverse<T>>: Sortable,
+{
+    let size = if config.record_size == 0 { T::SIZE } else { config.record_size };
+    if config.key_offset + T::SIZE > size {
+        return Err(invalid(format!(
+            "a {} byte key at offset {} doesn't fit in {} byte records",
+            T::SIZE,
+            config.key_offset,
+            size
+        )));
+    }
+    let bare = size == T::SIZE;
+    if !(if bare { opts.algo.supports::<T>() } else { opts.algo.supports::<Keyed<T>>() }) {
+        return Err(invalid(format!("{} can't sort these records, the SIMD algorithms only sort bare f32", opts.algo)));
+    }
+    let offset = config.key_offset;
+    let key = move |rec: &[u8]| T::from_le(&rec[offset..offset + T::SIZE]);
+    if opts.reverse {
+        let key = move |rec: &[u8]| Reverse(key(rec));
+        External::<T, _> { size, bare, opts, config, key, element: PhantomData }.sort(input, output)
+    } else {
+        External::<T, _> { size, bare, opts, config, key, element: PhantomData }.sort(input, output)
+    }
+}
+
+struct External<'a, T, F> {
+    size: usize,
+    /// the records are only the key, sorted as a plain array of `T`
+    bare: bool,
+    opts: &'a Options,
+    config: &'a Config,
+    /// `T` or `Reverse<T>` of a record
+    key: F,
+    element: PhantomData<fn() -> T>,
+}
+
+impl<T, K, F> External<'_, T, F>
+where
+    T: LeBytes + Sortable,
+    K: Ord + Copy,
+    F: Fn(&[u8]) -> K + Sync,
+    Keyed<K>: Sortable,
+{
+    fn sort(&self, mut input: impl Read, output: impl Write) -> io::Result<Stats> {
+        let mut output = BufWriter::new(output);
+        let mut stats = Stats::default();
+        // the chunk, its sorted pieces and their keys
+        let per_record = 2 * self.size + mem::size_of::<Keyed<K>>();
+        let chunk = (self.config.memory / per_record).max(1) * self.size;
+        let mut runs = vec![];
+        loop {
+            let data = read_chunk(&mut input, chunk)?;
+            if !data.len().is_multiple_of(self.size) {
+                return Err(invalid(format!("input is not a multiple of {} byte records", self.size)));
+            }
+            if data.is_empty() && !runs.is_empty() {
+                break;
+            }
+            let pieces = self.sort_pieces(&data);
+            let pieces: Vec<&[u8]> = pieces.iter().map(Vec::as_slice).collect();
+            // all of it fit in memory
+            if data.len() < chunk && runs.is_empty() {
+                stats.records = self.merge(pieces, &mut output)?;
+                output.flush()?;
+                return Ok(stats);
+            }
+            let (run, file) = Run::create(&self.config.tmp_dir)?;
+            let mut w = BufWriter::new(file);
+            self.merge(pieces, &mut w)?;
+            w.flush()?;
+            runs.push(run);
+            if data.len() < chunk {
+                break;
+            }
+        }
+        stats.runs = runs.len();
+
+        while runs.len() > FAN_IN {
+            let mut merged = vec![];
+            for group in runs.chunks(FAN_IN) {
+                let (run, file) = Run::create(&self.config.tmp_dir)?;
+                let mut w = BufWriter::new(file);
+                self.merge(self.open(group)?, &mut w)?;
+                w.flush()?;
+                merged.push(run);
+            }
+            runs = merged;
+            stats.passes += 1;
+        }
+        stats.records = self.merge(self.open(&runs)?, &mut output)?;
+        output.flush()?;
+        Ok(stats)
+    }
+
+    /// one sorted piece per rayon thread
+    fn sort_pieces(&self, data: &[u8]) -> Vec<Vec<u8>> {
+        let records = data.len() / self.size;
+        let per_piece = records.div_ceil(rayon::current_num_threads()).max(1);
+        data.par_chunks(per_piece * self.size).map(|piece| self.sort_piece(piece)).collect()
+    }
+
+    fn sort_piece(&self, piece: &[u8]) -> Vec<u8> {
+        if self.bare {
+            // equal bare keys are the same bytes, no index needed to keep them stable
+            return sort_array::<T>(piece, self.opts).expect("records and algorithm were checked");
+        }
+        let size = self.size;
+        let mut keyed = Keyed::decorate(piece.chunks_exact(size).map(&self.key));
+        self.opts.algo.sort(&mut keyed);
+        if self.opts.unique {
+            keyed.dedup_by(|a, b| a.key == b.key);
+        }
+        let mut out = Vec::with_capacity(keyed.len() * size);
+        for k in &keyed {
+            out.extend_from_slice(&piece[k.idx * size..][..size]);
+        }
+        out
+    }
+
+    fn open(&self, runs: &[Run]) -> io::Result<Vec<BufReader<File>>> {
+        let buffer = (self.config.memory / (runs.len() + 1)).max(MIN_BUFFER);
+        runs.iter().map(|run| Ok(BufReader::with_capacity(buffer, File::open(&run.0)?))).collect()
+    }
+
+    /// k-way merge of sorted sources, ties are taken from the earlier source
+    fn merge<R: BufRead>(&self, mut sources: Vec<R>, out: &mut impl Write) -> io::Result<u64> {
+        let size = self.size;
+        let mut records = vec![vec![0; size]; sources.len()];
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+        for (i, source) in sources.iter_mut().enumerate() {
+            if read_record(source, &mut records[i])? {
+                heap.push(Reverse(((self.key)(&records[i]), i)));
+            }
+        }
+        let mut last = None;
+        let mut written = 0;
+        while let Some(Reverse((key, i))) = heap.pop() {
+            if !(self.opts.unique && last == Some(key)) {
+                out.write_all(&records[i])?;
+                written += 1;
+                last = Some(key);
+            }
+            if read_record(&mut sources[i], &mut records[i])? {
+                heap.push(Reverse(((self.key)(&records[i]), i)));
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// read up to `len` bytes, fewer only at the end of `input`
+fn read_chunk(input: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(len);
+    input.take(len as u64).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// `false` at the end of `source`
+fn read_record(source: &mut impl BufRead, record: &mut [u8]) -> io::Result<bool> {
+    match source.read_exact(record) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// A sorted run spilled to disk, removed on drop
+struct Run(PathBuf);
+
+impl Run {
+    /// a new file in `dir` opened for writing, never one that exists already,
+    /// like a run left behind by a process with the same id or a planted link
+    fn create(dir: &std::path::Path) -> io::Result<(Run, File)> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        fs::create_dir_all(dir)?;
+        loop {
+            let name = format!("rusorts-{}-{}.run", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed));
+            let path = dir.join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((Run(path), file)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,354 @@
+use core::ffi::{c_char, c_int, c_void};
+use core::{ptr, slice};
+use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
+
+use once_cell::sync::Lazy;
+
+use crate::registry::{Algorithm, Sortable};
+use crate::ty::{DoubleOrd, FloatOrd};
+
+pub const RUSORTS_OK: c_int = 0;
+pub const RUSORTS_ERR_NULL: c_int = -1;
+pub const RUSORTS_ERR_ALGORITHM: c_int = -2;
+pub const RUSORTS_ERR_UNSUPPORTED: c_int = -3;
+pub const RUSORTS_ERR_PANIC: c_int = -4;
+
+/// (name, code, meaning) of the return codes
+const CODES: &[(&str, c_int, &str)] = &[
+    ("RUSORTS_OK", RUSORTS_OK, "sorted"),
+    ("RUSORTS_ERR_NULL", RUSORTS_ERR_NULL, "data is NULL and len is not 0"),
+    ("RUSORTS_ERR_ALGORITHM", RUSORTS_ERR_ALGORITHM, "not a RusortsAlgorithm"),
+    ("RUSORTS_ERR_UNSUPPORTED", RUSORTS_ERR_UNSUPPORTED, "the algorithm can't sort this element type"),
+    ("RUSORTS_ERR_PANIC", RUSORTS_ERR_PANIC, "the sort failed, the data is left in some order"),
+];
+
+static NAMES: Lazy<Vec<CString>> =
+    Lazy::new(|| Algorithm::ALL.iter().map(|a| CString::new(a.name()).unwrap()).collect());
+
+unsafe fn sort_raw<T: Sortable>(data: *mut T, len: usize, algorithm: u32) -> c_int {
+    let Some(&algo) = Algorithm::ALL.get(algorithm as usize) else {
+        return RUSORTS_ERR_ALGORITHM;
+    };
+    if !algo.supports::<T>() {
+        return RUSORTS_ERR_UNSUPPORTED;
+    }
+    if len == 0 {
+        return RUSORTS_OK;
+    }
+    if data.is_null() {
+        return RUSORTS_ERR_NULL;
+    }
+    let v = slice::from_raw_parts_mut(data, len);
+    // unwinding into C is undefined behavior, the SIMD sorts panic on NaN
+    match panic::catch_unwind(AssertUnwindSafe(|| algo.sort(v))) {
+        Ok(()) => RUSORTS_OK,
+        Err(_) => RUSORTS_ERR_PANIC,
+    }
+}
+
+macro_rules! impl_ffi_sort {
+    ($($name:ident: $c:literal, $t:ty => $ord:ty;)*) => {
+        $(
+            #[doc = concat!("Sort `len` `", $c, "`s at `data` ascending in place, see `include/rusorts.h`")]
+            ///
+            /// # Safety
+            ///
+            /// `data` must point to `len` initialized elements that nothing else accesses
+            /// during the call, it may be NULL if `len` is 0
+            #[no_mangle]
+            pub unsafe extern "C" fn $name(data: *mut $t, len: usize, algorithm: u32) -> c_int {
+                // `FloatOrd` and `DoubleOrd` are transparent
+                sort_raw(data.cast::<$ord>(), len, algorithm)
+            }
+        )*
+
+        /// (function, C element type) of every typed sort
+        const SORTS: &[(&str, &str)] = &[$((stringify!($name), $c),)*];
+    };
+}
+
+impl_ffi_sort!(
+    rusorts_sort_i32: "int32_t", i32 => i32;
+    rusorts_sort_u32: "uint32_t", u32 => u32;
+    rusorts_sort_i64: "int64_t", i64 => i64;
+    rusorts_sort_f32: "float", f32 => FloatOrd;
+    rusorts_sort_f64: "double", f64 => DoubleOrd;
+);
+
+/// Name of the algorithm like in `rust_sorts.algorithms()`, NULL if it is not a
+/// `RusortsAlgorithm`. The string is static, don't free it
+#[no_mangle]
+pub extern "C" fn rusorts_algorithm_name(algorithm: u32) -> *const c_char {
+    NAMES.get(algorithm as usize).map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// Number of algorithms of the library, to check it against `RUSORTS_ALGORITHM_COUNT`
+#[no_mangle]
+pub extern "C" fn rusorts_algorithm_count() -> u32 {
+    Algorithm::ALL.len() as u32
+}
+
+pub type Comparator = unsafe extern "C" fn(*const c_void, *const c_void) -> c_int;
+
+/// Drop-in for libc `qsort`: sort `n` elements of `size` bytes at `base` in
+/// place with an introsort, the block partition of the 1-Pivot Hoare Block
+/// Partition quicksort around a ninther pivot, a heapsort below a depth of
+/// 2 log2 n and insertion sort for short ranges. Elements are swapped through
+/// one element of scratch, the only extra memory besides the stack. A
+/// comparator that isn't a total order leaves them in some order, a panic
+/// aborts instead of unwinding into C
+///
+/// # Safety
+///
+/// same as `qsort`, `base` must point to `n` elements of `size` bytes that
+/// `cmp` can compare
+#[no_mangle]
+pub unsafe extern "C" fn rusorts_qsort(base: *mut c_void, n: usize, size: usize, cmp: Option<Comparator>) {
+    let (Some(cmp), Some(_)) = (cmp, n.checked_mul(size)) else {
+        return;
+    };
+    if base.is_null() || n < 2 || size == 0 {
+        return;
+    }
+    let base = base.cast::<u8>();
+    let sort = || {
+        let mut elements = Elements { base, size, cmp, tmp: vec![0; size] };
+        elements.introsort(0, n, 2 * (usize::BITS - n.leading_zeros()));
+    };
+    if panic::catch_unwind(AssertUnwindSafe(sort)).is_err() {
+        std::process::abort();
+    }
+}
+
+/// The array of `rusorts_qsort`, elements are addressed by index
+struct Elements {
+    base: *mut u8,
+    size: usize,
+    cmp: Comparator,
+    /// one element of scratch for swaps and insertion
+    tmp: Vec<u8>,
+}
+
+impl Elements {
+    const BLOCK: usize = 128;
+    const INSERTION_SORT_THRESHOLD: usize = 16;
+
+    unsafe fn at(&self, i: usize) -> *mut u8 {
+        self.base.add(i * self.size)
+    }
+
+    unsafe fn less(&self, i: usize, j: usize) -> bool {
+        (self.cmp)(self.at(i).cast(), self.at(j).cast()) < 0
+    }
+
+    unsafe fn swap(&mut self, i: usize, j: usize) {
+        if i != j {
+            let tmp = self.tmp.as_mut_ptr();
+            ptr::copy_nonoverlapping(self.at(i), tmp, self.size);
+            ptr::copy_nonoverlapping(self.at(j), self.at(i), self.size);
+            ptr::copy_nonoverlapping(tmp, self.at(j), self.size);
+        }
+    }
+
+    /// sort `lo..hi`, recursing into the shorter side of each partition
+    unsafe fn introsort(&mut self, mut lo: usize, mut hi: usize, mut limit: u32) {
+        loop {
+            if hi - lo < Self::INSERTION_SORT_THRESHOLD {
+                return self.insertion_sort(lo, hi);
+            }
+            if limit == 0 {
+                return self.heapsort(lo, hi);
+            }
+            limit -= 1;
+            let pivot = self.choose_pivot(lo, hi);
+            self.swap(lo, pivot);
+            let mid = self.partition(lo, hi);
+            if mid - lo < hi - mid {
+                self.introsort(lo, mid, limit);
+                lo = mid + 1;
+            } else {
+                self.introsort(mid + 1, hi, limit);
+                hi = mid;
+            }
+        }
+    }
+
+    unsafe fn insertion_sort(&mut self, lo: usize, hi: usize) {
+        let tmp = self.tmp.as_mut_ptr();
+        for i in lo + 1..hi {
+            let mut j = i;
+            ptr::copy_nonoverlapping(self.at(i), tmp, self.size);
+            while j > lo && (self.cmp)(tmp.cast(), self.at(j - 1).cast()) < 0 {
+                j -= 1;
+            }
+            if j < i {
+                ptr::copy(self.at(j), self.at(j + 1), (i - j) * self.size);
+                ptr::copy_nonoverlapping(tmp, self.at(j), self.size);
+            }
+        }
+    }
+
+    /// index of the median of 3 elements, of 3 medians of 3 (Tukey's ninther) in long ranges
+    unsafe fn choose_pivot(&self, lo: usize, hi: usize) -> usize {
+        let median3 = |a: usize, b: usize, c: usize| {
+            if self.less(a, b) {
+                if self.less(b, c) { b } else if self.less(a, c) { c } else { a }
+            } else if self.less(a, c) {
+                a
+            } else if self.less(b, c) {
+                c
+            } else {
+                b
+            }
+        };
+        let n = hi - lo;
+        let (a, b, c) = (lo + n / 4, lo + n / 2, lo + n / 4 * 3);
+        if n < 64 {
+            return median3(a, b, c);
+        }
+        median3(median3(a - 1, a, a + 1), median3(b - 1, b, b + 1), median3(c - 1, c, c + 1))
+    }
+
+    /// Partition `lo..hi` around the element at `lo`, returns where the pivot
+    /// ends up. Blocks of `BLOCK` elements from both ends are scanned into
+    /// offsets of misplaced elements which are then swapped in pairs, the
+    /// rest shorter than two blocks is partitioned element by element
+    unsafe fn partition(&mut self, lo: usize, hi: usize) -> usize {
+        let mut offsets_l = [0u8; Self::BLOCK];
+        let mut offsets_r = [0u8; Self::BLOCK];
+        let (mut start_l, mut num_l, mut start_r, mut num_r) = (0, 0, 0, 0);
+        // `lo + 1..l` is less than the pivot, `r..hi` is not
+        let (mut l, mut r) = (lo + 1, hi);
+
+        while r - l >= 2 * Self::BLOCK {
+            if num_l == 0 {
+                start_l = 0;
+                for i in 0..Self::BLOCK {
+                    offsets_l[num_l] = i as u8;
+                    num_l += !self.less(l + i, lo) as usize;
+                }
+            }
+            if num_r == 0 {
+                start_r = 0;
+                for i in 0..Self::BLOCK {
+                    offsets_r[num_r] = i as u8;
+                    num_r += self.less(r - 1 - i, lo) as usize;
+                }
+            }
+            let count = num_l.min(num_r);
+            for k in 0..count {
+                self.swap(l + offsets_l[start_l + k] as usize, r - 1 - offsets_r[start_r + k] as usize);
+            }
+            (start_l, num_l, start_r, num_r) = (start_l + count, num_l - count, start_r + count, num_r - count);
+            if num_l == 0 {
+                l += Self::BLOCK;
+            }
+            if num_r == 0 {
+                r -= Self::BLOCK;
+            }
+        }
+
+        // a block with offsets left over is scanned again, its swapped elements are in place
+        loop {
+            while l < r && self.less(l, lo) {
+                l += 1;
+            }
+            while l < r && !self.less(r - 1, lo) {
+                r -= 1;
+            }
+            if l >= r {
+                break;
+            }
+            self.swap(l, r - 1);
+            l += 1;
+            r -= 1;
+        }
+        self.swap(lo, l - 1);
+        l - 1
+    }
+
+    /// sift-down heapsort of `lo..hi`
+    unsafe fn heapsort(&mut self, lo: usize, hi: usize) {
+        let sift_down = |this: &mut Self, end: usize, mut node: usize| loop {
+            let mut child = 2 * node + 1;
+            if child >= end {
+                return;
+            }
+            if child + 1 < end && this.less(lo + child, lo + child + 1) {
+                child += 1;
+            }
+            if !this.less(lo + node, lo + child) {
+                return;
+            }
+            this.swap(lo + node, lo + child);
+            node = child;
+        };
+        let n = hi - lo;
+        for i in (0..n / 2).rev() {
+            sift_down(self, n, i);
+        }
+        for end in (1..n).rev() {
+            self.swap(lo, lo + end);
+            sift_down(self, end, 0);
+        }
+    }
+}
+
+/// Render `include/rusorts.h`, the algorithms are numbered in the order of
+/// `Algorithm::ALL` so new ones must be appended there
+pub fn header() -> String {
+    let mut h = String::new();
+    let mut line = |s: &str| {
+        h.push_str(s);
+        h.push('\n');
+    };
+    line("/* generated by `src/ffi.rs`, run `RUSORTS_BLESS=1 cargo test ffi` after changing it */");
+    line("#ifndef RUSORTS_H");
+    line("#define RUSORTS_H");
+    line("");
+    line("#include <stddef.h>");
+    line("#include <stdint.h>");
+    line("");
+    line("#ifdef __cplusplus");
+    line("extern \"C\" {");
+    line("#endif");
+    line("");
+    line("/* return codes of the rusorts_sort_* functions */");
+    for (name, code, meaning) in CODES {
+        line(&format!("#define {} {} /* {} */", name, code, meaning));
+    }
+    line("");
+    line("typedef enum RusortsAlgorithm {");
+    for (i, algo) in Algorithm::ALL.iter().enumerate() {
+        line(&format!("    RUSORTS_{} = {}, /* {} */", algo.name().to_uppercase(), i, algo.label()));
+    }
+    line(&format!("    RUSORTS_ALGORITHM_COUNT = {}", Algorithm::ALL.len()));
+    line("} RusortsAlgorithm;");
+    line("");
+    line("/* Sort `len` elements at `data` ascending in place with `algorithm`, a");
+    line(" * RusortsAlgorithm. Returns RUSORTS_OK or one of the RUSORTS_ERR_* codes,");
+    line(" * the SIMD algorithms only sort float. Floats are ordered like the");
+    line(" * `FloatOrd` wrapper of the crate, a NaN makes the SIMD algorithms fail");
+    line(" * with RUSORTS_ERR_PANIC. A library built with panic = \"abort\", like the");
+    line(" * release profile, aborts the process instead */");
+    for (name, ty) in SORTS {
+        line(&format!("int {}({} *data, size_t len, uint32_t algorithm);", name, ty));
+    }
+    line("");
+    line("/* name of the algorithm, NULL if out of range, the string is static */");
+    line("const char *rusorts_algorithm_name(uint32_t algorithm);");
+    line("uint32_t rusorts_algorithm_count(void);");
+    line("");
+    line("/* drop-in for qsort, an in-place introsort over the block partition of");
+    line(" * RUSORTS_HOARE_BLOCK, O(n log n) on any input. The only extra memory is one");
+    line(" * element of scratch. A panic aborts the process */");
+    line("void rusorts_qsort(void *base, size_t n, size_t size, int (*cmp)(const void *, const void *));");
+    line("");
+    line("#ifdef __cplusplus");
+    line("}");
+    line("#endif");
+    line("");
+    line("#endif /* RUSORTS_H */");
+    h
+}
@@ -0,0 +1,453 @@
+use core::cmp::Reverse;
+use core::fmt;
+use core::str::FromStr;
+use std::io::{self, Read};
+
+use crate::npy;
+use crate::registry::{Algorithm, Sortable};
+use crate::ty::{DoubleOrd, FloatOrd, Keyed};
+
+/// Layout of the input of the `rusorts` binary, the output has the same one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// one value per line
+    Lines,
+    /// rows of delimited columns, sorted by one of them
+    Csv,
+    /// raw little-endian array
+    Binary,
+    /// numpy `.npy` file, the header gives the type and shape
+    Npy,
+}
+
+impl Format {
+    pub const ALL: &'static [Format] = &[Format::Lines, Format::Csv, Format::Binary, Format::Npy];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Lines => "lines",
+            Format::Csv => "csv",
+            Format::Binary => "binary",
+            Format::Npy => "npy",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .iter()
+            .copied()
+            .find(|f| f.name() == s)
+            .ok_or_else(|| format!("unknown format `{}`", s))
+    }
+}
+
+/// Element type of a binary array, or type of the keys of text. Text keys are
+/// always parsed as i64, u64, f64 or str, `Auto` picks the first that fits all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Auto,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+    Str,
+}
+
+impl Type {
+    pub const ALL: &'static [Type] =
+        &[Type::Auto, Type::I32, Type::U32, Type::I64, Type::U64, Type::F32, Type::F64, Type::Str];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Type::Auto => "auto",
+            Type::I32 => "i32",
+            Type::U32 => "u32",
+            Type::I64 => "i64",
+            Type::U64 => "u64",
+            Type::F32 => "f32",
+            Type::F64 => "f64",
+            Type::Str => "str",
+        }
+    }
+
+    /// bytes of a binary element
+    pub fn size(self) -> Option<usize> {
+        match self {
+            Type::I32 | Type::U32 | Type::F32 => Some(4),
+            Type::I64 | Type::U64 | Type::F64 => Some(8),
+            Type::Auto | Type::Str => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Type {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Type::ALL
+            .iter()
+            .copied()
+            .find(|t| t.name() == s)
+            .ok_or_else(|| format!("unknown type `{}`", s))
+    }
+}
+
+/// Fixed size element of a little-endian binary array
+pub trait LeBytes: Sized {
+    const SIZE: usize;
+    const TYPE: Type;
+
+    /// `b` is exactly `SIZE` bytes
+    fn from_le(b: &[u8]) -> Self;
+    fn to_le(&self, out: &mut Vec<u8>);
+}
+
+macro_rules! impl_le_bytes {
+    ($($t:ty => $raw:ty, $ty:ident, |$x:ident| $wrap:expr, |$y:ident| $unwrap:expr);* $(;)?) => {
+        $(
+            impl LeBytes for $t {
+                const SIZE: usize = core::mem::size_of::<$raw>();
+                const TYPE: Type = Type::$ty;
+
+                #[inline]
+                fn from_le(b: &[u8]) -> Self {
+                    let $x = <$raw>::from_le_bytes(b.try_into().unwrap());
+                    $wrap
+                }
+
+                #[inline]
+                fn to_le(&self, out: &mut Vec<u8>) {
+                    let $y = self;
+                    out.extend_from_slice(&$unwrap.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_le_bytes!(
+    i32 => i32, I32, |x| x, |y| y;
+    u32 => u32, U32, |x| x, |y| y;
+    i64 => i64, I64, |x| x, |y| y;
+    u64 => u64, U64, |x| x, |y| y;
+    FloatOrd => f32, F32, |x| FloatOrd(x), |y| y.0;
+    DoubleOrd => f64, F64, |x| DoubleOrd(x), |y| y.0;
+);
+
+/// Evaluate `$body` with `$t` bound to the element type of a binary [`Type`],
+/// or `$otherwise` for the text only types
+macro_rules! with_binary_type {
+    ($ty: expr, |$t: ident| $body: expr, $otherwise: expr) => {{
+        use $crate::format::Type;
+        use $crate::ty::{DoubleOrd, FloatOrd};
+        match $ty {
+            Type::I32 => { type $t = i32; $body }
+            Type::U32 => { type $t = u32; $body }
+            Type::I64 => { type $t = i64; $body }
+            Type::U64 => { type $t = u64; $body }
+            Type::F32 => { type $t = FloatOrd; $body }
+            Type::F64 => { type $t = DoubleOrd; $body }
+            Type::Auto | Type::Str => $otherwise,
+        }
+    }};
+}
+
+pub(crate) use with_binary_type;
+
+/// How to sort, see `rusorts --help`
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub algo: Algorithm,
+    pub reverse: bool,
+    /// keep only the first of equal elements, or of rows with equal keys
+    pub unique: bool,
+    pub ty: Type,
+    pub delimiter: char,
+    /// the first CSV row names the columns and stays first
+    pub header: bool,
+    /// index from 0 or header name of the CSV column to sort by
+    pub key_column: String,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            algo: Algorithm::HoareBlock,
+            reverse: false,
+            unique: false,
+            ty: Type::Auto,
+            delimiter: ',',
+            header: false,
+            key_column: "0".to_string(),
+        }
+    }
+}
+
+/// Sort `input` laid out as `format`, the result has the same layout
+pub fn sort_bytes(input: Vec<u8>, format: Format, opts: &Options) -> Result<Vec<u8>, String> {
+    match format {
+        Format::Binary => with_binary_type!(opts.ty, |T| sort_array::<T>(&input, opts), {
+            Err(format!("binary input needs `--type`, one of i32, u32, i64, u64, f32 or f64, not {}", opts.ty))
+        }),
+        Format::Npy => {
+            let (mut header, data) = npy::read(&input[..]).map_err(|e| e.to_string())?;
+            let opts = Options { ty: header.ty, ..opts.clone() };
+            let sorted = with_binary_type!(header.ty, |T| sort_array::<T>(&data, &opts), unreachable!())?;
+            // the array is sorted flat, it keeps its shape unless `unique` dropped some
+            if sorted.len() != data.len() {
+                header.shape = vec![sorted.len() / header.ty.size().unwrap()];
+            }
+            let mut out = Vec::with_capacity(sorted.len() + 128);
+            npy::write(&mut out, &header, &sorted).map_err(|e| e.to_string())?;
+            Ok(out)
+        }
+        _ => {
+            let text = String::from_utf8(input).map_err(|e| format!("input is not UTF-8: {}", e))?;
+            sort_text(&text, format, opts).map(String::into_bytes)
+        }
+    }
+}
+
+/// Concatenate text inputs, each ends with a newline so the last line of one
+/// isn't glued to the first line of the next. With `header` only the first
+/// keeps its first line, the header of every CSV file after it is dropped
+pub fn chain_text<'a>(inputs: Vec<Box<dyn Read + 'a>>, header: bool) -> Box<dyn Read + 'a> {
+    let mut chained: Box<dyn Read + 'a> = Box::new(io::empty());
+    for (i, input) in inputs.into_iter().enumerate() {
+        let input = EndWithNewline { inner: input, last: None, done: false };
+        chained = if header && i > 0 {
+            Box::new(chained.chain(SkipLine { inner: input, skipped: false }))
+        } else {
+            Box::new(chained.chain(input))
+        };
+    }
+    chained
+}
+
+/// `inner` with a newline after it when it doesn't end with one
+struct EndWithNewline<R> {
+    inner: R,
+    last: Option<u8>,
+    done: bool,
+}
+
+impl<R: Read> Read for EndWithNewline<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.last = Some(buf[n - 1]);
+            return Ok(n);
+        }
+        self.done = true;
+        if self.last.is_some_and(|b| b != b'\n') {
+            buf[0] = b'\n';
+            return Ok(1);
+        }
+        Ok(0)
+    }
+}
+
+/// `inner` without its first line
+struct SkipLine<R> {
+    inner: R,
+    skipped: bool,
+}
+
+impl<R: Read> Read for SkipLine<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while !self.skipped {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            if let Some(i) = buf[..n].iter().position(|&b| b == b'\n') {
+                self.skipped = true;
+                buf.copy_within(i + 1..n, 0);
+                if n - i - 1 > 0 {
+                    return Ok(n - i - 1);
+                }
+            }
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// Sort a little-endian array of `T`
+pub fn sort_array<T: LeBytes + Sortable>(input: &[u8], opts: &Options) -> Result<Vec<u8>, String> {
+    if !input.len().is_multiple_of(T::SIZE) {
+        return Err(format!("input is {} bytes, not a multiple of {} bytes", input.len(), T::SIZE));
+    }
+    check_supports::<T>(opts.algo)?;
+    let mut v: Vec<T> = input.chunks_exact(T::SIZE).map(T::from_le).collect();
+    opts.algo.sort(&mut v);
+    if opts.reverse {
+        v.reverse();
+    }
+    if opts.unique {
+        v.dedup();
+    }
+    let mut out = Vec::with_capacity(v.len() * T::SIZE);
+    v.iter().for_each(|x| x.to_le(&mut out));
+    Ok(out)
+}
+
+fn check_supports<T: Sortable>(algo: Algorithm) -> Result<(), String> {
+    if algo.supports::<T>() {
+        Ok(())
+    } else {
+        Err(format!("{} can't sort this type, the SIMD algorithms only sort binary f32", algo))
+    }
+}
+
+/// Fields of a CSV row, double quotes around a field are removed and `""`
+/// inside them is a quote. Quoted fields can't span lines
+pub fn fields(row: &str, delimiter: char) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+/// Sort the lines of `text`, or the rows of a CSV by `opts.key_column`. The
+/// lines are written back unchanged, equal keys keep their order
+pub fn sort_text(text: &str, format: Format, opts: &Options) -> Result<String, String> {
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    if lines.last() == Some(&"") {
+        lines.pop();
+    }
+    let header = match format {
+        Format::Csv if opts.header && !lines.is_empty() => Some(lines.remove(0)),
+        _ => None,
+    };
+
+    // line numbers of the errors count from 1
+    let first = header.is_some() as usize + 1;
+    let keys: Vec<String> = match format {
+        Format::Csv => {
+            let col = match (opts.key_column.parse::<usize>(), header) {
+                (Ok(col), _) => col,
+                (Err(_), Some(header)) => fields(header.trim_end_matches('\r'), opts.delimiter)
+                    .iter()
+                    .position(|name| *name == opts.key_column)
+                    .ok_or_else(|| format!("no column `{}` in the header", opts.key_column))?,
+                (Err(_), None) => {
+                    return Err(format!("key column `{}` is not an index and there is no header", opts.key_column))
+                }
+            };
+            lines
+                .iter()
+                .enumerate()
+                .map(|(i, row)| {
+                    let mut row = fields(row.trim_end_matches('\r'), opts.delimiter);
+                    if col < row.len() {
+                        Ok(row.swap_remove(col))
+                    } else {
+                        Err(format!("line {}: no column {}", first + i, col))
+                    }
+                })
+                .collect::<Result<_, String>>()?
+        }
+        _ => lines.iter().map(|line| line.trim_end_matches('\r').to_string()).collect(),
+    };
+
+    let order = match parse_keys(keys, opts.ty, first)? {
+        Keys::Int(keys) => sort_keys(keys, opts)?,
+        Keys::UInt(keys) => sort_keys(keys, opts)?,
+        Keys::Float(keys) => sort_keys(keys, opts)?,
+        Keys::Str(keys) => sort_keys(keys, opts)?,
+    };
+
+    let mut out = String::with_capacity(text.len() + 1);
+    for line in header.into_iter().chain(order.into_iter().map(|i| lines[i])) {
+        out.push_str(line);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+enum Keys {
+    Int(Vec<i64>),
+    UInt(Vec<u64>),
+    Float(Vec<DoubleOrd>),
+    Str(Vec<String>),
+}
+
+/// every key as a `T`, the error names the first line, counted from `first`, that isn't one
+fn parse_all<T: FromStr>(keys: &[String], first: usize, ty: Type) -> Result<Vec<T>, String> {
+    keys.iter()
+        .enumerate()
+        .map(|(i, k)| k.trim().parse().map_err(|_| format!("line {}: `{}` is not {}", first + i, k, ty)))
+        .collect()
+}
+
+fn parse_keys(keys: Vec<String>, ty: Type, first: usize) -> Result<Keys, String> {
+    let floats = |v: Vec<f64>| Keys::Float(v.into_iter().map(DoubleOrd).collect());
+    match ty {
+        Type::Auto => Ok(parse_all(&keys, first, Type::I64)
+            .map(Keys::Int)
+            .or_else(|_| parse_all(&keys, first, Type::U64).map(Keys::UInt))
+            .or_else(|_| parse_all(&keys, first, Type::F64).map(floats))
+            .unwrap_or(Keys::Str(keys))),
+        Type::I64 => parse_all(&keys, first, ty).map(Keys::Int),
+        Type::U64 => parse_all(&keys, first, ty).map(Keys::UInt),
+        Type::F64 => parse_all(&keys, first, ty).map(floats),
+        Type::Str => Ok(Keys::Str(keys)),
+        _ => Err(format!("text keys are i64, u64, f64 or str, {} is only for binary input", ty)),
+    }
+}
+
+/// indices of the keys in sorted order, reversed keys keep equal keys in
+/// their original order like `sort -rs`
+fn sort_keys<K: Ord>(keys: Vec<K>, opts: &Options) -> Result<Vec<usize>, String>
+where
+    Keyed<K>: Sortable,
+    Keyed<Reverse<K>>: Sortable,
+{
+    check_supports::<Keyed<K>>(opts.algo)?;
+    let mut keyed = if opts.reverse {
+        let mut keyed = Keyed::decorate(keys.into_iter().map(Reverse));
+        opts.algo.sort(&mut keyed);
+        keyed.into_iter().map(|k| Keyed { key: k.key.0, idx: k.idx }).collect()
+    } else {
+        let mut keyed = Keyed::decorate(keys);
+        opts.algo.sort(&mut keyed);
+        keyed
+    };
+    if opts.unique {
+        keyed.dedup_by(|a, b| a.key == b.key);
+    }
+    Ok(keyed.into_iter().map(|k| k.idx).collect())
+}
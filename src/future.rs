@@ -0,0 +1,206 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use pyo3::exceptions::{PyRuntimeError, PyTimeoutError};
+use pyo3::iter::IterNextOutput;
+use pyo3::prelude::*;
+use pyo3::types::PyCFunction;
+
+use crate::buffer::NumBuffer;
+use crate::list::OrdNum;
+
+/// What the background thread hands back, turned into Python objects once
+/// the GIL is held again
+pub(crate) enum Sorted {
+    List(OrdNum),
+    /// sorted in place, the buffer is kept exported until the result is collected
+    Buffer(NumBuffer),
+}
+
+type Outcome = thread::Result<(Sorted, u64)>;
+
+#[derive(Default)]
+struct State {
+    outcome: Option<Outcome>,
+    /// the event loop and the `asyncio.Future` an `await` sleeps on
+    waiter: Option<(PyObject, PyObject)>,
+    /// the `SortFuture` is gone, nobody collects the outcome
+    abandoned: bool,
+}
+
+/// The state shared with the background thread, `finished` is notified once
+/// the outcome is in
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    finished: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// called by the background thread, Python objects are only dropped or
+    /// touched with the GIL held
+    fn finish(&self, outcome: Outcome) {
+        let (orphan, waiter) = {
+            let mut state = self.lock();
+            if state.abandoned {
+                (Some(outcome), None)
+            } else {
+                state.outcome = Some(outcome);
+                self.finished.notify_all();
+                (None, state.waiter.take())
+            }
+        };
+        if orphan.is_none() && waiter.is_none() {
+            return;
+        }
+        Python::with_gil(|py| {
+            drop(orphan);
+            if let Some((event_loop, future)) = waiter {
+                // the loop may be closed by now, then nothing is waiting anymore
+                let _ = wake(py, &event_loop, future);
+            }
+        });
+    }
+}
+
+/// resolve `future` on the thread of its event loop, unless it was cancelled
+fn wake(py: Python, event_loop: &PyObject, future: PyObject) -> PyResult<()> {
+    let set_result = PyCFunction::new_closure(py, None, None, move |args, _| -> PyResult<()> {
+        let future = future.as_ref(args.py());
+        if !future.call_method0("done")?.is_true()? {
+            future.call_method1("set_result", (args.py().None(),))?;
+        }
+        Ok(())
+    })?;
+    event_loop.call_method1(py, "call_soon_threadsafe", (set_result,))?;
+    Ok(())
+}
+
+/// Handle of a sort running on a background thread, the result is the same
+/// `(sorted, ns)` tuple the blocking functions return.
+///
+/// Works like a `concurrent.futures.Future` (`done()`, `result(timeout)`) and
+/// can be awaited from asyncio
+#[pyclass]
+pub struct SortFuture {
+    obj: PyObject,
+    shared: Arc<Shared>,
+    result: Option<Result<PyObject, String>>,
+}
+
+impl SortFuture {
+    pub(crate) fn spawn(obj: PyObject, sort: impl FnOnce() -> (Sorted, u64) + Send + 'static) -> SortFuture {
+        let shared = Arc::new(Shared::default());
+        let theirs = shared.clone();
+        thread::spawn(move || theirs.finish(panic::catch_unwind(AssertUnwindSafe(sort))));
+        SortFuture { obj, shared, result: None }
+    }
+
+    /// turn the outcome into Python objects, the sort must be done
+    fn collect(&mut self, py: Python) -> PyResult<PyObject> {
+        if self.result.is_none() {
+            let outcome = self.shared.lock().outcome.take().expect("the sort is not done");
+            self.result = Some(match outcome {
+                Ok((sorted, ns)) => {
+                    let sorted = match sorted {
+                        Sorted::List(list) => list.into_list(py).into(),
+                        Sorted::Buffer(buf) => {
+                            drop(buf);
+                            self.obj.clone_ref(py)
+                        }
+                    };
+                    Ok((sorted, ns).into_py(py))
+                }
+                Err(_) => Err("the sort panicked".to_string()),
+            });
+        }
+        match self.result.as_ref().unwrap() {
+            Ok(result) => Ok(result.clone_ref(py)),
+            Err(e) => Err(PyRuntimeError::new_err(e.clone())),
+        }
+    }
+}
+
+impl Drop for SortFuture {
+    // runs with the GIL held, an outcome that is already in is dropped here
+    // and a later one by the background thread under the GIL
+    fn drop(&mut self) {
+        let outcome = {
+            let mut state = self.shared.lock();
+            state.abandoned = true;
+            state.waiter = None;
+            state.outcome.take()
+        };
+        drop(outcome);
+    }
+}
+
+#[pymethods]
+impl SortFuture {
+    fn done(&self) -> bool {
+        self.result.is_some() || self.shared.lock().outcome.is_some()
+    }
+
+    /// block until the sort is done, at most `timeout` seconds
+    #[pyo3(signature = (timeout = None))]
+    fn result(&mut self, py: Python, timeout: Option<f64>) -> PyResult<PyObject> {
+        if self.result.is_none() {
+            let shared = &self.shared;
+            let finished = py.allow_threads(|| {
+                let state = shared.lock();
+                let running = |state: &mut State| state.outcome.is_none();
+                match timeout {
+                    Some(timeout) => {
+                        let timeout = Duration::from_secs_f64(timeout.max(0.0));
+                        let (state, _) = shared.finished.wait_timeout_while(state, timeout, running).unwrap();
+                        state.outcome.is_some()
+                    }
+                    None => shared.finished.wait_while(state, running).unwrap().outcome.is_some(),
+                }
+            });
+            if !finished {
+                return Err(PyTimeoutError::new_err(format!(
+                    "the sort did not finish within {}s",
+                    timeout.unwrap_or_default()
+                )));
+            }
+        }
+        self.collect(py)
+    }
+
+    fn __await__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    /// sleep on an `asyncio.Future` of the running loop until the background
+    /// thread resolves it
+    fn __next__(&mut self, py: Python) -> PyResult<IterNextOutput<PyObject, PyObject>> {
+        if self.done() {
+            return Ok(IterNextOutput::Return(self.collect(py)?));
+        }
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let future = event_loop.call_method0("create_future")?;
+        {
+            let mut state = self.shared.lock();
+            if state.outcome.is_none() {
+                state.waiter = Some((event_loop.into(), future.into()));
+            } else {
+                drop(state);
+                return Ok(IterNextOutput::Return(self.collect(py)?));
+            }
+        }
+        // what `asyncio.Future.__await__` sets before yielding itself to the task
+        future.setattr("_asyncio_future_blocking", true)?;
+        Ok(IterNextOutput::Yield(future.into()))
+    }
+}
@@ -0,0 +1,229 @@
+use core::mem;
+
+use pyo3::exceptions::{PyOverflowError, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyFloat, PyList, PyLong, PyString, PyTuple};
+
+use crate::registry::Algorithm;
+use crate::ty::{Bytes, DoubleOrd, Field, FloatOrd, Tuple};
+
+/// A Python list as a `Vec` of a single element type, sorted exactly: ints as
+/// int64 (uint64 if they only fit there), floats as float64, str, bytes and
+/// tuples of those
+pub(crate) enum OrdNum {
+    Int(Vec<i64>),
+    UInt(Vec<u64>),
+    Float(Vec<DoubleOrd>),
+    Str(Vec<String>),
+    Bytes(Vec<Bytes>),
+    Tuple(Vec<Tuple>),
+}
+
+/// Evaluate `$body` with `$v` bound to the `Vec` of an `OrdNum`, `$body` must
+/// compile for every element type
+macro_rules! with_ord_vec {
+    ($ord: expr, |$v: ident| $body: expr) => {{
+        use $crate::list::OrdNum;
+        match $ord {
+            OrdNum::Int($v) => $body,
+            OrdNum::UInt($v) => $body,
+            OrdNum::Float($v) => $body,
+            OrdNum::Str($v) => $body,
+            OrdNum::Bytes($v) => $body,
+            OrdNum::Tuple($v) => $body,
+        }
+    }};
+}
+
+pub(crate) use with_ord_vec;
+
+impl OrdNum {
+    pub fn into_list(self, py: Python<'_>) -> &PyList {
+        with_ord_vec!(self, |v| PyList::new(py, v))
+    }
+
+    /// the list if `algo` sorts it exactly, the SIMD sorts only take float32
+    /// and narrowing the floats would round them
+    pub fn exact_for(self, algo: Algorithm) -> PyResult<OrdNum> {
+        match self {
+            OrdNum::Float(_) if algo.is_simd() => Err(PyTypeError::new_err(format!(
+                "{} only sorts float32, a list of floats is float64, sort a float32 buffer like array.array('f', v) instead",
+                algo
+            ))),
+            list => Ok(list),
+        }
+    }
+}
+
+fn unexpected(i: usize, expected: &str, item: &PyAny) -> PyErr {
+    match item.get_type().name() {
+        Ok(name) => PyTypeError::new_err(format!("expected {} at index {}, got {}", expected, i, name)),
+        Err(e) => e,
+    }
+}
+
+fn extract_all<T>(list: &PyList, f: impl Fn(usize, &PyAny) -> PyResult<T>) -> PyResult<Vec<T>> {
+    list.iter().enumerate().map(|(i, item)| f(i, item)).collect()
+}
+
+/// an int that isn't a bool, sorting bools as ints would turn them into 0 and 1
+fn is_int(item: &PyAny) -> bool {
+    item.is_instance_of::<PyLong>() && !item.is_instance_of::<PyBool>()
+}
+
+/// all ints or all floats like the first item, a float among ints or an int
+/// among floats can't be sorted exactly as either
+fn extract_nums(list: &PyList) -> PyResult<OrdNum> {
+    let floats = list.get_item(0)?.is_instance_of::<PyFloat>();
+    for (i, item) in list.iter().enumerate() {
+        if floats && !item.is_instance_of::<PyFloat>() {
+            return Err(unexpected(i, "float", item));
+        } else if !floats && !is_int(item) {
+            return Err(unexpected(i, "int", item));
+        }
+    }
+    if floats {
+        return extract_all(list, |_, item| item.extract().map(DoubleOrd)).map(OrdNum::Float);
+    }
+    let mut v = Vec::with_capacity(list.len());
+    for (i, item) in list.iter().enumerate() {
+        match item.extract::<i64>() {
+            Ok(n) => v.push(n),
+            Err(_) => return extract_uints(list, i),
+        }
+    }
+    Ok(OrdNum::Int(v))
+}
+
+/// the int at index `big` is too big for int64, all of them have to fit in uint64
+fn extract_uints(list: &PyList, big: usize) -> PyResult<OrdNum> {
+    extract_all(list, |i, item| {
+        item.extract::<u64>().map_err(|_| {
+            PyOverflowError::new_err(if item.extract::<i64>().is_ok() {
+                format!("int at index {} is negative but the int at index {} only fits in uint64", i, big)
+            } else {
+                format!("int at index {} does not fit in int64 or uint64", i)
+            })
+        })
+    })
+    .map(OrdNum::UInt)
+}
+
+fn extract_tuples(list: &PyList) -> PyResult<OrdNum> {
+    let tuples = extract_all(list, |i, item| match item.downcast::<PyTuple>() {
+        Ok(tuple) => tuple.iter().enumerate().map(|(j, field)| extract_field(i, j, field)).collect::<PyResult<_>>().map(Tuple),
+        Err(_) => Err(unexpected(i, "tuple", item)),
+    })?;
+    // the index of the first tuple long enough to have each position
+    let mut first: Vec<usize> = vec![];
+    for (i, tuple) in tuples.iter().enumerate() {
+        for (j, field) in tuple.0.iter().enumerate() {
+            if j == first.len() {
+                first.push(i);
+            }
+            let like = &tuples[first[j]].0[j];
+            if mem::discriminant(field) != mem::discriminant(like) {
+                return Err(PyTypeError::new_err(format!(
+                    "expected {} at position {} of the tuple at index {}, like the tuple at index {}, got {}",
+                    like.kind(),
+                    j,
+                    i,
+                    first[j],
+                    field.kind()
+                )));
+            }
+        }
+    }
+    Ok(OrdNum::Tuple(tuples))
+}
+
+impl FromPyObject<'_> for OrdNum {
+    fn extract(ob: &PyAny) -> PyResult<Self> {
+        let list = ob.downcast::<PyList>()?;
+        let Ok(first) = list.get_item(0) else {
+            return Ok(OrdNum::Int(vec![]));
+        };
+        if first.is_instance_of::<PyLong>() || first.is_instance_of::<PyFloat>() {
+            extract_nums(list)
+        } else if first.is_instance_of::<PyString>() {
+            extract_all(list, |i, item| match item.downcast::<PyString>() {
+                Ok(s) => s.extract(),
+                Err(_) => Err(unexpected(i, "str", item)),
+            })
+            .map(OrdNum::Str)
+        } else if first.is_instance_of::<PyBytes>() {
+            extract_all(list, |i, item| match item.downcast::<PyBytes>() {
+                Ok(b) => Ok(Bytes(b.as_bytes().to_vec())),
+                Err(_) => Err(unexpected(i, "bytes", item)),
+            })
+            .map(OrdNum::Bytes)
+        } else if first.is_instance_of::<PyTuple>() {
+            extract_tuples(list)
+        } else {
+            Err(unexpected(0, "int, float, str, bytes or tuple", first))
+        }
+    }
+}
+
+impl FromPyObject<'_> for FloatOrd {
+    fn extract(ob: &PyAny) -> PyResult<Self> {
+        Ok(FloatOrd(ob.extract()?))
+    }
+}
+
+impl ToPyObject for FloatOrd {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.0.to_object(py)
+    }
+}
+
+/// field `j` of the tuple at index `i`
+fn extract_field(i: usize, j: usize, item: &PyAny) -> PyResult<Field> {
+    if item.is_instance_of::<PyFloat>() {
+        item.extract().map(|f| Field::Float(DoubleOrd(f)))
+    } else if is_int(item) {
+        item.extract().map(Field::Int).map_err(|_| {
+            PyOverflowError::new_err(format!("int at position {} of the tuple at index {} does not fit in 128 bits", j, i))
+        })
+    } else if let Ok(s) = item.downcast::<PyString>() {
+        s.extract().map(Field::Str)
+    } else if let Ok(b) = item.downcast::<PyBytes>() {
+        Ok(Field::Bytes(Bytes(b.as_bytes().to_vec())))
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "expected int, float, str or bytes at position {} of the tuple at index {}, got {}",
+            j,
+            i,
+            item.get_type().name()?
+        )))
+    }
+}
+
+impl ToPyObject for DoubleOrd {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.0.to_object(py)
+    }
+}
+
+impl ToPyObject for Bytes {
+    fn to_object(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.0).into()
+    }
+}
+
+impl ToPyObject for Field {
+    fn to_object(&self, py: Python) -> PyObject {
+        match self {
+            Field::Int(n) => n.to_object(py),
+            Field::Float(f) => f.to_object(py),
+            Field::Str(s) => s.to_object(py),
+            Field::Bytes(b) => b.to_object(py),
+        }
+    }
+}
+
+impl ToPyObject for Tuple {
+    fn to_object(&self, py: Python) -> PyObject {
+        PyTuple::new(py, &self.0).into()
+    }
+}
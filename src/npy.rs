@@ -0,0 +1,189 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::format::{LeBytes, Type};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// The header is padded so the data starts at a multiple of this
+const ALIGN: usize = 64;
+
+/// Header of a `.npy` file, only little-endian int32, uint32, int64, uint64,
+/// float32 and float64 arrays in C order are supported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub ty: Type,
+    pub shape: Vec<usize>,
+}
+
+impl Header {
+    /// header of a 1-d array
+    pub fn new(ty: Type, len: usize) -> Header {
+        Header { ty, shape: vec![len] }
+    }
+
+    /// number of elements, an error if the shape overflows `usize`
+    pub fn len(&self) -> io::Result<usize> {
+        self.shape
+            .iter()
+            .try_fold(1usize, |n, &d| n.checked_mul(d))
+            .ok_or_else(|| invalid(format!(".npy shape {:?} is too large", self.shape)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shape.contains(&0)
+    }
+
+    /// bytes of the data after the header
+    pub fn data_len(&self) -> io::Result<usize> {
+        self.len()?
+            .checked_mul(self.ty.size().unwrap_or(0))
+            .ok_or_else(|| invalid(format!(".npy shape {:?} is too large", self.shape)))
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// numpy `descr` of the element type
+pub fn descr(ty: Type) -> Option<&'static str> {
+    match ty {
+        Type::I32 => Some("<i4"),
+        Type::U32 => Some("<u4"),
+        Type::I64 => Some("<i8"),
+        Type::U64 => Some("<u8"),
+        Type::F32 => Some("<f4"),
+        Type::F64 => Some("<f8"),
+        Type::Auto | Type::Str => None,
+    }
+}
+
+/// the value of `key` in the header dict, a quoted string, a tuple or a literal
+fn value<'a>(dict: &'a str, key: &str) -> io::Result<&'a str> {
+    let missing = || invalid(format!(".npy header has no `{}`: {}", key, dict));
+    let start = dict.find(&format!("'{}'", key)).ok_or_else(missing)? + key.len() + 2;
+    let rest = dict[start..].trim_start().strip_prefix(':').ok_or_else(missing)?.trim_start();
+    let end = match rest.chars().next() {
+        Some('\'') => rest[1..].find('\'').map(|i| i + 2),
+        Some('(') => rest.find(')').map(|i| i + 1),
+        _ => rest.find([',', '}']),
+    };
+    Ok(&rest[..end.ok_or_else(missing)?])
+}
+
+fn parse_dict(dict: &str) -> io::Result<Header> {
+    let dict = dict.replace('"', "'");
+    let d = value(&dict, "descr")?.trim_matches('\'');
+    let ty = Type::ALL
+        .iter()
+        .copied()
+        .find(|&t| descr(t) == Some(d))
+        .ok_or_else(|| invalid(format!("unsupported .npy dtype `{}`, only little-endian i4, u4, i8, u8, f4 and f8", d)))?;
+    let shape = value(&dict, "shape")?
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.trim_end_matches('L').parse().map_err(|_| invalid(format!("bad .npy shape in {}", dict))))
+        .collect::<io::Result<Vec<usize>>>()?;
+    if value(&dict, "fortran_order")? == "True" && shape.len() > 1 {
+        return Err(invalid("only C order .npy arrays are supported".to_string()));
+    }
+    let header = Header { ty, shape };
+    header.data_len()?;
+    Ok(header)
+}
+
+/// Read the magic, the version and the header, `r` is left at the data
+pub fn read_header(r: &mut impl Read) -> io::Result<Header> {
+    let mut prefix = [0; 8];
+    r.read_exact(&mut prefix)?;
+    if &prefix[..6] != MAGIC {
+        return Err(invalid("not a .npy file".to_string()));
+    }
+    let len = match prefix[6] {
+        1 => {
+            let mut len = [0; 2];
+            r.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0; 4];
+            r.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        v => return Err(invalid(format!("unsupported .npy version {}", v))),
+    };
+    let mut dict = vec![0; len];
+    r.read_exact(&mut dict)?;
+    parse_dict(&String::from_utf8_lossy(&dict))
+}
+
+/// Write a version 1 header, or 2 if it doesn't fit
+pub fn write_header(w: &mut impl Write, header: &Header) -> io::Result<()> {
+    let descr = descr(header.ty).ok_or_else(|| invalid(format!("{} is not a numpy dtype", header.ty)))?;
+    let shape = match header.shape.as_slice() {
+        [n] => format!("({},)", n),
+        shape => format!("({})", shape.iter().map(usize::to_string).collect::<Vec<_>>().join(", ")),
+    };
+    let mut dict = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+    // spaces and a newline up to the alignment
+    let padded = |prefix: usize| (prefix + dict.len() + 1).next_multiple_of(ALIGN) - prefix;
+    let v1 = padded(MAGIC.len() + 4) <= u16::MAX as usize;
+    let padded = padded(MAGIC.len() + if v1 { 4 } else { 6 });
+    dict.extend(std::iter::repeat_n(' ', padded - dict.len() - 1));
+    dict.push('\n');
+
+    w.write_all(MAGIC)?;
+    if v1 {
+        w.write_all(&[1, 0])?;
+        w.write_all(&(padded as u16).to_le_bytes())?;
+    } else {
+        w.write_all(&[2, 0])?;
+        w.write_all(&(padded as u32).to_le_bytes())?;
+    }
+    w.write_all(dict.as_bytes())
+}
+
+/// Read a whole `.npy` file, the data is the raw little-endian array and must
+/// be exactly as long as the header says
+pub fn read(mut r: impl Read) -> io::Result<(Header, Vec<u8>)> {
+    let header = read_header(&mut r)?;
+    let len = header.data_len()?;
+    // the header is not trusted to size the buffer, it grows with what is read
+    let mut data = vec![];
+    r.by_ref().take(len as u64).read_to_end(&mut data)?;
+    if data.len() < len {
+        return Err(invalid(format!(".npy data is {} bytes, the header says {}", data.len(), len)));
+    }
+    if r.read(&mut [0])? != 0 {
+        return Err(invalid(format!(".npy data is longer than the {} bytes the header says", len)));
+    }
+    Ok((header, data))
+}
+
+pub fn write(mut w: impl Write, header: &Header, data: &[u8]) -> io::Result<()> {
+    write_header(&mut w, header)?;
+    w.write_all(data)
+}
+
+/// Load a `.npy` file of `T`s, flattened
+pub fn load<T: LeBytes>(path: &Path) -> io::Result<Vec<T>> {
+    let (header, data) = read(BufReader::new(File::open(path)?))?;
+    if header.ty != T::TYPE {
+        return Err(invalid(format!("{} holds {}, not {}", path.display(), header.ty, T::TYPE)));
+    }
+    Ok(data.chunks_exact(T::SIZE).map(T::from_le).collect())
+}
+
+/// Save `v` as a 1-d `.npy` file
+pub fn save<T: LeBytes>(path: &Path, v: &[T]) -> io::Result<()> {
+    let mut data = Vec::with_capacity(v.len() * T::SIZE);
+    v.iter().for_each(|x| x.to_le(&mut data));
+    let mut w = BufWriter::new(File::create(path)?);
+    write(&mut w, &Header::new(T::TYPE, v.len()), &data)?;
+    w.flush()
+}
@@ -0,0 +1,153 @@
+use core::fmt;
+use core::str::FromStr;
+use std::time::Instant;
+
+use crate::registry::Algorithm;
+
+/// Hardware events `measure` can count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    CpuCycle,
+    Instruction,
+    CacheMiss,
+    BranchMiss,
+}
+
+impl Event {
+    pub const ALL: &'static [Event] = &[Event::CpuCycle, Event::Instruction, Event::CacheMiss, Event::BranchMiss];
+
+    /// same names as the `Measurement`s of the benches
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::CpuCycle => "cpu_cycle",
+            Event::Instruction => "instruction",
+            Event::CacheMiss => "cache_miss",
+            Event::BranchMiss => "branch_miss",
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Event {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Event::ALL
+            .iter()
+            .copied()
+            .find(|e| e.name() == s)
+            .ok_or_else(|| format!("unknown event `{}`", s))
+    }
+}
+
+/// Wall time of one call plus the events that could be counted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sample {
+    pub ns: u64,
+    pub counts: Vec<(Event, u64)>,
+}
+
+impl Sample {
+    /// `None` if the event wasn't requested or perf isn't available
+    pub fn get(&self, event: Event) -> Option<u64> {
+        self.counts.iter().find(|(e, _)| *e == event).map(|&(_, n)| n)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod counter {
+    use perfcnt::linux::{HardwareEventType as Hardware, PerfCounterBuilderLinux as Builder};
+    use perfcnt::{AbstractPerfCounter, PerfCounter};
+
+    use super::Event;
+
+    pub(super) struct Counter(PerfCounter);
+
+    impl Counter {
+        /// user space only, so it works with `perf_event_paranoid` up to 2
+        pub fn open(event: Event) -> Option<Counter> {
+            let hardware = match event {
+                Event::CpuCycle => Hardware::CPUCycles,
+                Event::Instruction => Hardware::Instructions,
+                Event::CacheMiss => Hardware::CacheMisses,
+                Event::BranchMiss => Hardware::BranchMisses,
+            };
+            Builder::from_hardware_event(hardware)
+                .disable()
+                .exclude_kernel()
+                .exclude_hv()
+                .finish()
+                .ok()
+                .map(Counter)
+        }
+
+        pub fn start(&self) -> bool {
+            self.0.reset().is_ok() && self.0.start().is_ok()
+        }
+
+        pub fn stop(&mut self) -> Option<u64> {
+            self.0.stop().ok()?;
+            self.0.read().ok()
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod counter {
+    use super::Event;
+
+    pub(super) struct Counter;
+
+    impl Counter {
+        pub fn open(_event: Event) -> Option<Counter> {
+            None
+        }
+
+        pub fn start(&self) -> bool {
+            false
+        }
+
+        pub fn stop(&mut self) -> Option<u64> {
+            None
+        }
+    }
+}
+
+/// The `events` that `measure` counts right for `algo`, none for the parallel
+/// sorts since the counters miss the work of the rayon threads
+pub fn events_for(algo: Algorithm, events: &[Event]) -> &[Event] {
+    if algo.is_parallel() {
+        &[]
+    } else {
+        events
+    }
+}
+
+/// Run `sort` on `data` once, timing it and counting `events` with perf_event.
+///
+/// Events that can't be counted (no perf support, `perf_event_paranoid` too
+/// high, no PMU in a VM) are left out of the sample, the wall time is
+/// always there. The counters only follow the calling thread, pass the
+/// events through `events_for` to leave them out of parallel sorts
+pub fn measure<T>(sort: impl FnOnce(&mut [T]), data: &mut [T], events: &[Event]) -> Sample {
+    let mut counters: Vec<_> = events
+        .iter()
+        .filter_map(|&e| counter::Counter::open(e).map(|c| (e, c)))
+        .collect();
+    counters.retain(|(_, c)| c.start());
+
+    let start = Instant::now();
+    sort(data);
+    let ns = start.elapsed().as_nanos() as u64;
+
+    let counts = counters
+        .iter_mut()
+        .filter_map(|(e, c)| c.stop().map(|n| (*e, n)))
+        .collect();
+    Sample { ns, counts }
+}
@@ -0,0 +1,66 @@
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+/// pools kept for reuse, the least recently used one is dropped beyond that
+pub const MAX_POOLS: usize = 4;
+
+/// pools by number of threads, built on first use, the most recently used last
+static POOLS: Mutex<Vec<(usize, Arc<ThreadPool>)>> = Mutex::new(Vec::new());
+/// the pool of `set_num_threads`, rayon's global pool if None
+static DEFAULT: RwLock<Option<Arc<ThreadPool>>> = RwLock::new(None);
+
+/// A pool of `threads` threads shared by everyone asking for that many,
+/// 0 is one per core like rayon's global pool. Only the `MAX_POOLS` most
+/// recently used pools are kept, the others live as long as their users
+pub fn with_threads(threads: usize) -> Result<Arc<ThreadPool>, ThreadPoolBuildError> {
+    let mut pools = POOLS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(i) = pools.iter().position(|&(n, _)| n == threads) {
+        let used = pools.remove(i);
+        pools.push(used);
+        return Ok(pools[pools.len() - 1].1.clone());
+    }
+    let pool = Arc::new(
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(move |i| format!("rusorts-{}-{}", threads, i))
+            .build()?,
+    );
+    if pools.len() == MAX_POOLS {
+        pools.remove(0);
+    }
+    pools.push((threads, pool.clone()));
+    Ok(pool)
+}
+
+/// Run the parallel sorts of the bindings on `threads` threads from now on,
+/// or on rayon's global pool if None
+pub fn set_num_threads(threads: Option<usize>) -> Result<(), ThreadPoolBuildError> {
+    let pool = threads.map(with_threads).transpose()?;
+    *DEFAULT.write().unwrap_or_else(PoisonError::into_inner) = pool;
+    Ok(())
+}
+
+/// threads of the pool the bindings sort on by default
+pub fn num_threads() -> usize {
+    match &*DEFAULT.read().unwrap_or_else(PoisonError::into_inner) {
+        Some(pool) => pool.current_num_threads(),
+        None => rayon::current_num_threads(),
+    }
+}
+
+/// the pool of `threads` threads, or the one of `set_num_threads`
+pub fn get(threads: Option<usize>) -> Result<Option<Arc<ThreadPool>>, ThreadPoolBuildError> {
+    match threads {
+        Some(threads) => with_threads(threads).map(Some),
+        None => Ok(DEFAULT.read().unwrap_or_else(PoisonError::into_inner).clone()),
+    }
+}
+
+/// run `op` in `pool`, or in the calling thread with rayon's global pool
+pub fn install<R: Send>(pool: Option<&ThreadPool>, op: impl FnOnce() -> R + Send) -> R {
+    match pool {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
@@ -0,0 +1,160 @@
+use core::cmp::Ordering;
+use core::{mem, ptr};
+
+use crate::{branchsim, cachesim};
+use crate::registry::{self, Algorithm, Sortable};
+
+/// Hooks called by the sorts in `qsort.rs` on every element load and store,
+/// and on every branch that depends on comparing elements.
+///
+/// Every type gets the no-op default, so the plain sorts compile exactly as
+/// before, only [`Traced`] elements report their accesses to the simulators
+pub trait Probe {
+    fn load(p: *const Self);
+    fn store(p: *const Self);
+    /// after the loads and stores of a swap
+    fn swapped();
+    /// `site` identifies the branch, the source line in `qsort.rs`
+    fn branch(site: u32, taken: bool);
+}
+
+impl<T> Probe for T {
+    #[inline(always)]
+    default fn load(_p: *const Self) {}
+
+    #[inline(always)]
+    default fn store(_p: *const Self) {}
+
+    #[inline(always)]
+    default fn swapped() {}
+
+    #[inline(always)]
+    default fn branch(_site: u32, _taken: bool) {}
+}
+
+/// report a branch of the sort of `T` elements, returns `taken`
+#[inline(always)]
+pub(crate) fn branch<T>(site: u32, taken: bool) -> bool {
+    T::branch(site, taken);
+    taken
+}
+
+/// `slice::swap` that reports the two loads and stores
+#[inline(always)]
+pub(crate) fn swap<T>(arr: &mut [T], a: usize, b: usize) {
+    assert!(a < arr.len() && b < arr.len());
+    unsafe { swap_unchecked(arr, a, b) }
+}
+
+/// # Safety
+///
+/// `a` and `b` must be in bounds
+#[inline(always)]
+pub(crate) unsafe fn swap_unchecked<T>(arr: &mut [T], a: usize, b: usize) {
+    let p = arr.as_ptr();
+    T::load(p.add(a));
+    T::load(p.add(b));
+    T::store(p.add(a));
+    T::store(p.add(b));
+    T::swapped();
+    arr.swap_unchecked(a, b);
+}
+
+/// # Safety
+///
+/// same as `ptr::swap`
+#[inline(always)]
+pub(crate) unsafe fn swap_ptr<T>(a: *mut T, b: *mut T) {
+    T::load(a);
+    T::load(b);
+    T::store(a);
+    T::store(b);
+    T::swapped();
+    ptr::swap(a, b);
+}
+
+/// # Safety
+///
+/// same as `ptr::read`
+#[inline(always)]
+pub(crate) unsafe fn read<T>(src: *const T) -> T {
+    T::load(src);
+    ptr::read(src)
+}
+
+/// # Safety
+///
+/// same as `ptr::copy_nonoverlapping`, for a single element
+#[inline(always)]
+pub(crate) unsafe fn copy_nonoverlapping<T>(src: *const T, dst: *mut T) {
+    T::load(src);
+    T::store(dst);
+    ptr::copy_nonoverlapping(src, dst, 1);
+}
+
+/// Instrumented element, comparisons and moves of `Traced` values are
+/// replayed through the simulators installed on the current thread
+#[repr(transparent)]
+#[derive(Debug, Clone, Default)]
+pub struct Traced<T>(pub T);
+
+impl<T> Traced<T> {
+    /// view a slice as traced elements without copying it
+    pub fn slice(v: &mut [T]) -> &mut [Traced<T>] {
+        // Traced is repr(transparent)
+        unsafe { &mut *(v as *mut [T] as *mut [Traced<T>]) }
+    }
+}
+
+impl<T> Probe for Traced<T> {
+    #[inline]
+    fn load(p: *const Self) {
+        cachesim::access(p as usize, mem::size_of::<T>());
+    }
+
+    #[inline]
+    fn store(p: *const Self) {
+        cachesim::access(p as usize, mem::size_of::<T>());
+    }
+
+    #[inline]
+    fn branch(site: u32, taken: bool) {
+        branchsim::branch(site, taken);
+    }
+}
+
+impl<T: Ord> PartialEq for Traced<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Traced<T> {}
+
+impl<T: Ord> PartialOrd for Traced<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Traced<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        Self::load(self);
+        Self::load(other);
+        self.0.cmp(&other.0)
+    }
+}
+
+// only the sorts of `qsort.rs` go through the probes, std and rayon move
+// elements behind our back and the SIMD sorts work on raw f32
+impl<T: Ord + Send> Sortable for Traced<T> {
+    fn supports(algo: Algorithm) -> bool {
+        !algo.is_parallel() && !algo.is_simd() && algo != Algorithm::StdUnstable
+    }
+
+    fn sort_with(algo: Algorithm, v: &mut [Self]) {
+        assert!(Self::supports(algo), "{} can not be traced", algo);
+        registry::sort_generic(algo, v)
+    }
+}
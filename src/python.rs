@@ -0,0 +1,703 @@
+use crate::buffer::{self, with_dtype, with_num_slice, NumBuffer};
+use crate::dist::Dist;
+use crate::future::{SortFuture, Sorted};
+use crate::list::{with_ord_vec, OrdNum};
+use crate::perf::{self, Event};
+use crate::pool;
+use crate::registry::{Algorithm, Sortable};
+use crate::timing::{self, Timing};
+use crate::ty::*;
+use crate::util::{self, *};
+
+use pyo3::{
+    exceptions::{PyRuntimeError, PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyDict, PyList},
+};
+use rayon::ThreadPool;
+use std::cmp::Reverse;
+use std::sync::Arc;
+
+
+#[pyfunction]
+#[pyo3(signature = (v, threads = None, timing = false))]
+fn par_pdqsort<'a>(py: Python<'a>, v: &'a PyAny, threads: Option<usize>, timing: bool) -> PyResult<(&'a PyAny, PyObject)> {
+    legacy_sort(py, thread_pool(threads)?.as_deref(), v, Algorithm::ParPdqsort, timing)
+}
+
+#[pyfunction]
+#[pyo3(signature = (v, threads = None, timing = false))]
+fn par_crumsort<'a>(py: Python<'a>, v: &'a PyAny, threads: Option<usize>, timing: bool) -> PyResult<(&'a PyAny, PyObject)> {
+    legacy_sort(py, thread_pool(threads)?.as_deref(), v, Algorithm::ParCrumsort, timing)
+}
+
+#[pyfunction]
+#[pyo3(signature = (v, timing = false))]
+fn single_pivot_quicksort_hoare_block_partition<'a>(
+    py: Python<'a>,
+    v: &'a PyAny,
+    timing: bool,
+) -> PyResult<(&'a PyAny, PyObject)> {
+    legacy_sort(py, None, v, Algorithm::HoareBlock, timing)
+}
+
+#[pyfunction]
+#[pyo3(signature = (v, timing = false))]
+fn quadro_pivot_quicksort<'a>(
+    py: Python<'a>,
+    v: &'a PyAny,
+    timing: bool,
+) -> PyResult<(&'a PyAny, PyObject)> {
+    legacy_sort(py, None, v, Algorithm::QuadPivot, timing)
+}
+
+/// the ns of one sort, or a `Timing` of it with the list conversion kept apart
+fn legacy_sort<'a>(
+    py: Python<'a>,
+    pool: Option<&ThreadPool>,
+    v: &'a PyAny,
+    algo: Algorithm,
+    timing: bool,
+) -> PyResult<(&'a PyAny, PyObject)> {
+    if timing {
+        let (v, t) = time_list_or_buffer(py, pool, v, algo, 0, 1)?;
+        return Ok((v, t.into_py(py)));
+    }
+    let (v, ns) = sort_list_or_buffer(py, pool, v, algo, false)?;
+    Ok((v, ns.into_py(py)))
+}
+
+/// `rust_sorts::ty::Keyed<alloc::string::String>` -> `Keyed<String>`
+fn short_type_name<T>() -> String {
+    let mut name = String::new();
+    let mut path = String::new();
+    for c in std::any::type_name::<T>().chars().chain(Some(' ')) {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+        } else {
+            name.push_str(path.rsplit("::").next().unwrap_or_default());
+            name.push(c);
+            path.clear();
+        }
+    }
+    name.pop();
+    name
+}
+
+/// the pool of `threads` threads, or the one of `set_num_threads`
+fn thread_pool(threads: Option<usize>) -> PyResult<Option<Arc<ThreadPool>>> {
+    if threads == Some(0) {
+        return Err(PyValueError::new_err("threads must be at least 1"));
+    }
+    pool::get(threads).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+/// run `op` with the GIL released, the parallel sorts on `pool`
+fn run<R: Send>(py: Python, pool: Option<&ThreadPool>, op: impl FnOnce() -> R + Send) -> R {
+    py.allow_threads(|| pool::install(pool, op))
+}
+
+/// Run the sorts on a pool of `threads` threads from now on, on every core if None
+#[pyfunction]
+#[pyo3(signature = (threads = None))]
+fn set_num_threads(threads: Option<usize>) -> PyResult<()> {
+    if threads == Some(0) {
+        return Err(PyValueError::new_err("threads must be at least 1"));
+    }
+    pool::set_num_threads(threads).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+#[pyfunction]
+fn num_threads() -> usize {
+    pool::num_threads()
+}
+
+fn check_supports<T: Sortable>(algo: Algorithm) -> PyResult<()> {
+    if !algo.supports::<T>() {
+        return Err(PyValueError::new_err(format!("{} does not support {}", algo, short_type_name::<T>())));
+    }
+    Ok(())
+}
+
+fn measure_sortable<T: Sortable>(
+    py: Python,
+    pool: Option<&ThreadPool>,
+    algo: Algorithm,
+    v: &mut [T],
+    events: &[Event],
+) -> PyResult<perf::Sample> {
+    check_supports::<T>(algo)?;
+    Ok(run(py, pool, || perf::measure(|v| algo.sort(v), v, perf::events_for(algo, events))))
+}
+
+#[pyfunction]
+#[pyo3(signature = (v, algorithm = "hoare_block", events = None, threads = None))]
+fn measure<'a>(
+    py: Python<'a>,
+    v: &'a PyAny,
+    algorithm: &str,
+    events: Option<Vec<&str>>,
+    threads: Option<usize>,
+) -> PyResult<(&'a PyAny, &'a PyDict)> {
+    let algo = algorithm.parse::<Algorithm>().map_err(PyValueError::new_err)?;
+    let pool = thread_pool(threads)?;
+    let pool = pool.as_deref();
+    let events = match events {
+        Some(events) => events
+            .iter()
+            .map(|e| e.parse::<Event>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(PyValueError::new_err)?,
+        None => Event::ALL.to_vec(),
+    };
+    let (v, sample) = match v.downcast::<PyList>() {
+        Ok(list) => {
+            let mut list = list.extract::<OrdNum>()?.exact_for(algo)?;
+            let sample = with_ord_vec!(&mut list, |v| measure_sortable(py, pool, algo, v, &events))?;
+            (list.into_list(py).as_ref(), sample)
+        }
+        Err(_) => {
+            let buf = NumBuffer::get(v)?;
+            (v, with_num_slice!(buf, |s| measure_sortable(py, pool, algo, s, &events))?)
+        }
+    };
+    let counts = PyDict::new(py);
+    counts.set_item("ns", sample.ns)?;
+    for (event, n) in sample.counts {
+        counts.set_item(event.name(), n)?;
+    }
+    Ok((v, counts))
+}
+
+#[pymethods]
+impl Timing {
+    #[getter(runs)]
+    fn py_runs(&self) -> usize {
+        self.runs()
+    }
+
+    #[getter(mean)]
+    fn py_mean(&self) -> f64 {
+        self.mean()
+    }
+
+    /// sample standard deviation
+    #[getter(sd)]
+    fn py_sd(&self) -> f64 {
+        self.sd()
+    }
+
+    #[getter(median)]
+    fn py_median(&self) -> f64 {
+        self.median()
+    }
+
+    #[getter(min)]
+    fn py_min(&self) -> u64 {
+        self.min()
+    }
+
+    #[getter(max)]
+    fn py_max(&self) -> u64 {
+        self.max()
+    }
+
+    /// `p` in 0 to 100
+    #[pyo3(name = "percentile")]
+    fn py_percentile(&self, p: f64) -> f64 {
+        self.percentile(p)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Timing(mean={:.0}ns, sd={:.0}ns, median={:.0}ns, min={}ns, runs={}, outliers={})",
+            self.mean(),
+            self.sd(),
+            self.median(),
+            self.min(),
+            self.runs(),
+            self.outliers.len()
+        )
+    }
+}
+
+fn time_sortable<T: Sortable + Clone>(
+    py: Python,
+    pool: Option<&ThreadPool>,
+    algo: Algorithm,
+    v: &mut [T],
+    warmup: usize,
+    runs: usize,
+) -> PyResult<Timing> {
+    check_supports::<T>(algo)?;
+    Ok(run(py, pool, || timing::time_sort(v, warmup, runs, |v| algo.sort(v))))
+}
+
+#[pyfunction]
+#[pyo3(signature = (v, algorithm = "hoare_block", warmup = 1, runs = 10, threads = None))]
+fn time_sort<'a>(
+    py: Python<'a>,
+    v: &'a PyAny,
+    algorithm: &str,
+    warmup: usize,
+    runs: usize,
+    threads: Option<usize>,
+) -> PyResult<(&'a PyAny, Timing)> {
+    let algo = algorithm.parse::<Algorithm>().map_err(PyValueError::new_err)?;
+    if runs == 0 {
+        return Err(PyValueError::new_err("runs must be at least 1"));
+    }
+    time_list_or_buffer(py, thread_pool(threads)?.as_deref(), v, algo, warmup, runs)
+}
+
+fn time_list_or_buffer<'a>(
+    py: Python<'a>,
+    pool: Option<&ThreadPool>,
+    v: &'a PyAny,
+    algo: Algorithm,
+    warmup: usize,
+    runs: usize,
+) -> PyResult<(&'a PyAny, Timing)> {
+    if v.downcast::<PyList>().is_err() {
+        // sorted in place, nothing to convert
+        let buf = NumBuffer::get(v)?;
+        return Ok((v, with_num_slice!(buf, |s| time_sortable(py, pool, algo, s, warmup, runs))?));
+    }
+    let mut extracted = None;
+    let extract_ns = time_it(|| extracted = Some(v.extract::<OrdNum>().and_then(|list| list.exact_for(algo))));
+    let mut extracted = extracted.unwrap()?;
+    let timing = with_ord_vec!(&mut extracted, |v| time_sortable(py, pool, algo, v, warmup, runs))?;
+    let mut list = None;
+    let convert_ns = time_it(|| list = Some(extracted.into_list(py)));
+    Ok((list.unwrap().as_ref(), Timing { extract_ns, convert_ns, ..timing }))
+}
+
+fn sort_sortable<T: Sortable>(
+    py: Python,
+    pool: Option<&ThreadPool>,
+    algo: Algorithm,
+    v: &mut [T],
+    reverse: bool,
+) -> PyResult<u64> {
+    check_supports::<T>(algo)?;
+    Ok(run(py, pool, || {
+        time_it(|| {
+            algo.sort(v);
+            if reverse {
+                v.reverse();
+            }
+        })
+    }))
+}
+
+/// sort the keys with their index and return the indices, reversed keys keep
+/// equal elements in their original order like `sorted(reverse=True)`
+fn sort_keys<K>(
+    py: Python,
+    pool: Option<&ThreadPool>,
+    algo: Algorithm,
+    keys: impl IntoIterator<Item = K>,
+    reverse: bool,
+) -> PyResult<(Vec<usize>, u64)>
+where
+    Keyed<K>: Sortable,
+    Keyed<Reverse<K>>: Sortable,
+{
+    fn sort_decorated<K>(
+        py: Python,
+        pool: Option<&ThreadPool>,
+        algo: Algorithm,
+        mut keyed: Vec<Keyed<K>>,
+    ) -> PyResult<(Vec<usize>, u64)>
+    where
+        Keyed<K>: Sortable,
+    {
+        let t = sort_sortable(py, pool, algo, &mut keyed, false)?;
+        Ok((keyed.into_iter().map(|k| k.idx).collect(), t))
+    }
+    if reverse {
+        sort_decorated(py, pool, algo, Keyed::decorate(keys.into_iter().map(Reverse)))
+    } else {
+        sort_decorated(py, pool, algo, Keyed::decorate(keys))
+    }
+}
+
+/// lists are copied into a new sorted list, buffers are sorted in place and returned as is
+fn sort_list_or_buffer<'a>(
+    py: Python<'a>,
+    pool: Option<&ThreadPool>,
+    v: &'a PyAny,
+    algo: Algorithm,
+    reverse: bool,
+) -> PyResult<(&'a PyAny, u64)> {
+    match v.downcast::<PyList>() {
+        Ok(list) => {
+            let mut list = list.extract::<OrdNum>()?.exact_for(algo)?;
+            let t = with_ord_vec!(&mut list, |v| sort_sortable(py, pool, algo, v, reverse))?;
+            Ok((list.into_list(py).as_ref(), t))
+        }
+        Err(_) => {
+            let buf = NumBuffer::get(v)?;
+            Ok((v, with_num_slice!(buf, |s| sort_sortable(py, pool, algo, s, reverse))?))
+        }
+    }
+}
+
+/// the stable permutation that sorts a list of keys
+fn argsort_list(
+    py: Python,
+    pool: Option<&ThreadPool>,
+    algo: Algorithm,
+    keys: &PyList,
+    reverse: bool,
+) -> PyResult<(Vec<usize>, u64)> {
+    if algo.is_simd() {
+        return Err(PyValueError::new_err(format!("{} only sorts float32 values, it can't sort indices", algo)));
+    }
+    let keys = keys.extract::<OrdNum>()?;
+    with_ord_vec!(keys, |keys| sort_keys(py, pool, algo, keys, reverse))
+}
+
+/// `key(x)` of every item, or the items themselves
+fn keys_of<'a>(py: Python<'a>, items: &[&'a PyAny], key: Option<&PyAny>) -> PyResult<&'a PyList> {
+    Ok(match key {
+        Some(key) => PyList::new(py, items.iter().map(|&x| key.call1((x,))).collect::<PyResult<Vec<_>>>()?),
+        None => PyList::new(py, items),
+    })
+}
+
+/// decorate-sort-undecorate, `key` is called once per element
+fn sort_by_key<'a>(
+    py: Python<'a>,
+    pool: Option<&ThreadPool>,
+    v: &'a PyAny,
+    algo: Algorithm,
+    key: &PyAny,
+    reverse: bool,
+) -> PyResult<(&'a PyAny, u64)> {
+    let items = v.iter()?.collect::<PyResult<Vec<_>>>()?;
+    let (order, t) = argsort_list(py, pool, algo, keys_of(py, &items, Some(key))?, reverse)?;
+    Ok((PyList::new(py, order.into_iter().map(|i| items[i])).as_ref(), t))
+}
+
+/// Sort with any algorithm of the registry, see `algorithms()` for the names
+#[pyfunction]
+#[pyo3(signature = (v, algorithm = "hoare_block", key = None, reverse = false, threads = None))]
+fn sort<'a>(
+    py: Python<'a>,
+    v: &'a PyAny,
+    algorithm: &str,
+    key: Option<&PyAny>,
+    reverse: bool,
+    threads: Option<usize>,
+) -> PyResult<(&'a PyAny, u64)> {
+    let algo = algorithm.parse::<Algorithm>().map_err(PyValueError::new_err)?;
+    let pool = thread_pool(threads)?;
+    match key {
+        Some(key) => sort_by_key(py, pool.as_deref(), v, algo, key, reverse),
+        None => sort_list_or_buffer(py, pool.as_deref(), v, algo, reverse),
+    }
+}
+
+/// name, label and capabilities of every algorithm
+#[pyfunction]
+fn algorithms(py: Python<'_>) -> PyResult<&PyList> {
+    let list = PyList::empty(py);
+    for &algo in Algorithm::ALL {
+        let info = PyDict::new(py);
+        info.set_item("name", algo.name())?;
+        info.set_item("label", algo.label())?;
+        info.set_item("parallel", algo.is_parallel())?;
+        info.set_item("simd", algo.is_simd())?;
+        info.set_item("dtypes", buffer::dtypes(algo))?;
+        list.append(info)?;
+    }
+    Ok(list)
+}
+
+/// Sort a list or buffer in place and return the ns taken. Lists are sorted by
+/// index like `key=`, so they keep their own objects
+#[pyfunction]
+#[pyo3(signature = (v, algorithm = "hoare_block", key = None, reverse = false, threads = None))]
+fn sort_inplace(
+    py: Python,
+    v: &PyAny,
+    algorithm: &str,
+    key: Option<&PyAny>,
+    reverse: bool,
+    threads: Option<usize>,
+) -> PyResult<u64> {
+    let algo = algorithm.parse::<Algorithm>().map_err(PyValueError::new_err)?;
+    let pool = thread_pool(threads)?;
+    let pool = pool.as_deref();
+    let Ok(list) = v.downcast::<PyList>() else {
+        if key.is_some() {
+            return Err(PyTypeError::new_err("key can only be used with a list"));
+        }
+        return sort_list_or_buffer(py, pool, v, algo, reverse).map(|(_, t)| t);
+    };
+    let items: Vec<_> = list.iter().collect();
+    let (order, t) = argsort_list(py, pool, algo, keys_of(py, &items, key)?, reverse)?;
+    // `key` may have changed the list, every item must still be in its place
+    if list.len() != items.len() || list.iter().zip(&items).any(|(now, &was)| !now.is(was)) {
+        return Err(PyValueError::new_err("list modified during sort"));
+    }
+    for (i, j) in order.into_iter().enumerate() {
+        list.set_item(i, items[j])?;
+    }
+    Ok(t)
+}
+
+/// `order` as an int64 `array.array`, or a numpy array if `like` is one
+fn index_array<'a>(py: Python<'a>, order: Vec<usize>, like: &PyAny) -> PyResult<&'a PyAny> {
+    let order: Vec<i64> = order.into_iter().map(|i| i as i64).collect();
+    let array = unsafe { buffer::new_array(py, "int64", &order)? };
+    // numpy can't have made `like` if it isn't imported
+    if let Ok(numpy) = py.import("sys")?.getattr("modules")?.get_item("numpy") {
+        if like.is_instance(numpy.getattr("ndarray")?)? {
+            return numpy.call_method1("frombuffer", (array, "int64"));
+        }
+    }
+    Ok(array)
+}
+
+/// The stable permutation that sorts a list or buffer, like `numpy.argsort(kind="stable")`,
+/// returned with the ns taken
+#[pyfunction]
+#[pyo3(signature = (v, algorithm = "hoare_block", reverse = false, threads = None))]
+fn argsort<'a>(
+    py: Python<'a>,
+    v: &'a PyAny,
+    algorithm: &str,
+    reverse: bool,
+    threads: Option<usize>,
+) -> PyResult<(&'a PyAny, u64)> {
+    let algo = algorithm.parse::<Algorithm>().map_err(PyValueError::new_err)?;
+    let pool = thread_pool(threads)?;
+    let pool = pool.as_deref();
+    let (order, t) = match v.downcast::<PyList>() {
+        Ok(list) => argsort_list(py, pool, algo, list, reverse)?,
+        Err(_) => {
+            let buf = NumBuffer::get(v)?;
+            if algo.is_simd() {
+                return Err(PyValueError::new_err(format!("{} only sorts float32 values, it can't sort indices", algo)));
+            }
+            with_num_slice!(buf, |s| sort_keys(py, pool, algo, s.iter().copied(), reverse))?
+        }
+    };
+    Ok((index_array(py, order, v)?, t))
+}
+
+/// Time the algorithms on data generated in Rust, columns of equal length
+/// (a `pandas.DataFrame(result)` is the table of the result CSVs, in μs)
+#[pyfunction]
+#[pyo3(signature = (
+    algorithms = None,
+    sizes = vec![1_000, 10_000, 100_000],
+    distribution = "uniform",
+    runs = 10,
+    seed = None,
+    dtype = "float32",
+    warmup = 1,
+    threads = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn benchmark<'a>(
+    py: Python<'a>,
+    algorithms: Option<Vec<&str>>,
+    sizes: Vec<usize>,
+    distribution: &str,
+    runs: usize,
+    seed: Option<u64>,
+    dtype: &str,
+    warmup: usize,
+    threads: Option<usize>,
+) -> PyResult<&'a PyDict> {
+    let dist = distribution.parse::<Dist>().map_err(PyValueError::new_err)?;
+    let pool = thread_pool(threads)?;
+    if runs == 0 {
+        return Err(PyValueError::new_err("runs must be at least 1"));
+    }
+    let (algos, explicit) = match algorithms {
+        Some(names) => (
+            names
+                .iter()
+                .map(|a| a.parse::<Algorithm>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(PyValueError::new_err)?,
+            true,
+        ),
+        None => (Algorithm::ALL.to_vec(), false),
+    };
+    let seed = seed.unwrap_or_else(util::seed);
+    let results = with_dtype!(dtype, |T| {
+        // every algorithm by default, only those that support the dtype
+        if explicit {
+            algos.iter().try_for_each(|&algo| check_supports::<T>(algo))?;
+        }
+        Ok(run(py, pool.as_deref(), || timing::benchmark::<T>(&algos, &sizes, dist, warmup, runs, seed)))
+    })?;
+
+    let columns = PyDict::new(py);
+    let column = |name: &str, values: &dyn Fn(&(Algorithm, usize, Timing)) -> PyObject| {
+        columns.set_item(name, PyList::new(py, results.iter().map(values)))
+    };
+    column("Method", &|(algo, _, _)| algo.label().into_py(py))?;
+    column("Algorithm", &|(algo, _, _)| algo.name().into_py(py))?;
+    column("Size", &|&(_, size, _)| size.into_py(py))?;
+    // μs like the result CSVs
+    column("Mean", &|(_, _, t)| (t.mean() * 1e-3).into_py(py))?;
+    column("SD", &|(_, _, t)| (t.sd() * 1e-3).into_py(py))?;
+    column("Median", &|(_, _, t)| (t.median() * 1e-3).into_py(py))?;
+    column("Min", &|(_, _, t)| (t.min() as f64 * 1e-3).into_py(py))?;
+    column("Max", &|(_, _, t)| (t.max() as f64 * 1e-3).into_py(py))?;
+    column("Outliers", &|(_, _, t)| t.outliers.len().into_py(py))?;
+    column("Type", &|_| dtype.into_py(py))?;
+    column("Dist", &|_| dist.name().into_py(py))?;
+    Ok(columns)
+}
+
+/// Data of the same seeded distributions as the Rust benches, as an `array.array`
+#[pyfunction]
+#[pyo3(signature = (distribution, n, seed = None, dtype = "float32"))]
+fn generate<'a>(py: Python<'a>, distribution: &str, n: usize, seed: Option<u64>, dtype: &str) -> PyResult<&'a PyAny> {
+    let dist = distribution.parse::<Dist>().map_err(PyValueError::new_err)?;
+    let seed = seed.unwrap_or_else(util::seed);
+    with_dtype!(dtype, |T| {
+        let v: Vec<T> = py.allow_threads(|| dist.generate(n, seed));
+        unsafe { buffer::new_array(py, dtype, &v) }
+    })
+}
+
+/// names of the distributions `generate` and `benchmark` take
+#[pyfunction]
+fn distributions() -> Vec<&'static str> {
+    Dist::ALL.iter().map(|d| d.name()).collect()
+}
+
+/// `check_supports` for the element type of a list
+fn check_list_supports(algo: Algorithm, list: &OrdNum) -> PyResult<()> {
+    match list {
+        OrdNum::Int(_) => check_supports::<i64>(algo),
+        OrdNum::UInt(_) => check_supports::<u64>(algo),
+        OrdNum::Float(_) => check_supports::<DoubleOrd>(algo),
+        OrdNum::Str(_) => check_supports::<String>(algo),
+        OrdNum::Bytes(_) => check_supports::<Bytes>(algo),
+        OrdNum::Tuple(_) => check_supports::<Tuple>(algo),
+    }
+}
+
+/// `check_supports` for the element type of a buffer
+fn check_buffer_supports(algo: Algorithm, buf: &NumBuffer) -> PyResult<()> {
+    match buf {
+        NumBuffer::I8(_) => check_supports::<i8>(algo),
+        NumBuffer::I16(_) => check_supports::<i16>(algo),
+        NumBuffer::I32(_) => check_supports::<i32>(algo),
+        NumBuffer::I64(_) => check_supports::<i64>(algo),
+        NumBuffer::U8(_) => check_supports::<u8>(algo),
+        NumBuffer::U16(_) => check_supports::<u16>(algo),
+        NumBuffer::U32(_) => check_supports::<u32>(algo),
+        NumBuffer::U64(_) => check_supports::<u64>(algo),
+        NumBuffer::F32(_) => check_supports::<FloatOrd>(algo),
+        NumBuffer::F64(_) => check_supports::<DoubleOrd>(algo),
+    }
+}
+
+/// Like the blocking functions, but the sort runs on a background thread
+#[pyfunction]
+#[pyo3(signature = (v, algorithm = "hoare_block", threads = None))]
+fn sort_async(v: &PyAny, algorithm: &str, threads: Option<usize>) -> PyResult<SortFuture> {
+    let algo = algorithm.parse::<Algorithm>().map_err(PyValueError::new_err)?;
+    let pool = thread_pool(threads)?;
+    let obj = v.into();
+    if let Ok(list) = v.downcast::<PyList>() {
+        let mut list = list.extract::<OrdNum>()?.exact_for(algo)?;
+        check_list_supports(algo, &list)?;
+        return Ok(SortFuture::spawn(obj, move || {
+            let t = pool::install(pool.as_deref(), || with_ord_vec!(&mut list, |v| time_it(|| algo.sort(v))));
+            (Sorted::List(list), t)
+        }));
+    }
+    let buf = NumBuffer::get(v)?;
+    check_buffer_supports(algo, &buf)?;
+    Ok(SortFuture::spawn(obj, move || {
+        let t = pool::install(pool.as_deref(), || with_num_slice!(buf, |s| time_it(|| algo.sort(s))));
+        (Sorted::Buffer(buf), t)
+    }))
+}
+
+#[pymodule]
+#[pyo3(name = "rust_sorts")]
+fn rust_sorts(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(par_pdqsort, m)?)?;
+    m.add_function(wrap_pyfunction!(par_crumsort, m)?)?;
+    m.add_function(wrap_pyfunction!(single_pivot_quicksort_hoare_block_partition, m)?)?;
+    m.add_function(wrap_pyfunction!(quadro_pivot_quicksort, m)?)?;
+    m.add_function(wrap_pyfunction!(sort, m)?)?;
+    m.add_function(wrap_pyfunction!(sort_inplace, m)?)?;
+    m.add_function(wrap_pyfunction!(argsort, m)?)?;
+    m.add_function(wrap_pyfunction!(algorithms, m)?)?;
+    m.add_function(wrap_pyfunction!(set_num_threads, m)?)?;
+    m.add_function(wrap_pyfunction!(num_threads, m)?)?;
+    m.add_function(wrap_pyfunction!(measure, m)?)?;
+    m.add_function(wrap_pyfunction!(time_sort, m)?)?;
+    m.add_function(wrap_pyfunction!(benchmark, m)?)?;
+    m.add_function(wrap_pyfunction!(generate, m)?)?;
+    m.add_function(wrap_pyfunction!(distributions, m)?)?;
+    m.add_function(wrap_pyfunction!(sort_async, m)?)?;
+    m.add_class::<Timing>()?;
+    m.add_class::<SortFuture>()?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add("__doc__", r#"Rust sorting algorithms, ffi by PyO3
+"""
+Params:
+    v (list | buffer): List to be sorted, all ints (sorted as int64, or uint64 when they only fit
+        there), floats (float64, a TypeError for the SIMD algorithms), str, bytes or tuples of those
+        with the same kind of field at each position, a mixed (ints and floats too, bools are not
+        ints) or out of range list raises a TypeError or an OverflowError naming the index. Or a writable C contiguous buffer (numpy array,
+        array.array, memoryview, ...) of int8 to int64, uint8 to uint64, float32 or float64 in native
+        byte order that is sorted in place without copying, the SIMD algorithms only sort float32 buffers
+    timing (bool): Return a Timing of the sort instead of the ns, with the list conversion
+        overhead in extract_ns and convert_ns like time_sort
+Returns:
+    (list | buffer, int | Timing): Sorted list (the buffer itself) and the cost time
+Functions available:
+par_pdqsort: -> uses par_sort_unstable (PDQSort) boosted by Rayon
+par_crumsort -> uses parallel Crumsort boosted by Rayon 
+single_pivot_quicksort_hoare_block_partition -> uses quick_sort_hoare_partition_block
+quadro_pivot_quicksort -> uses 4-Pivots QuickSort
+set_num_threads(threads=None) -> runs the parallel sorts on a pool of that many threads from now
+    on, or on every core if None, num_threads() returns the current number. Every sorting function
+    also takes threads= to use a pool of that many threads for one call
+sort(v, algorithm="hoare_block", key=None, reverse=False) -> sorts with any algorithm of the registry,
+    descending if reverse. With a key, any iterable is sorted into a new list by key(x), called once
+    per element, the keys are sorted together with their index so equal keys keep their original
+    order (stable like sorted) whatever the algorithm, the SIMD algorithms can't sort by key.
+    Without a key the values themselves are sorted, equal values can't be told apart
+sort_inplace(v, algorithm="hoare_block", key=None, reverse=False) -> sorts the list or buffer in
+    place and returns the ns, a list is reordered by index like with key so it keeps its objects
+argsort(v, algorithm="hoare_block", reverse=False) -> returns the stable permutation that sorts v as
+    an int64 array.array (a numpy array if v is one) and the ns, to reorder other columns by v
+algorithms() -> a dict per algorithm with its "name", "label", whether it is "parallel" or
+    "simd" and the "dtypes" it supports
+measure(v, algorithm="hoare_block", events=None) -> sorts with any algorithm of the registry,
+    returns the sorted list and a dict with the "ns" taken plus "cpu_cycle", "instruction",
+    "cache_miss" and "branch_miss" counts, the events perf can't count are left out and so are
+    all of them for the parallel algorithms, perf only follows the calling thread
+time_sort(v, algorithm="hoare_block", warmup=1, runs=10) -> returns the sorted list and a Timing
+    with mean, sd, median, min, max and percentile(p) of the runs in ns, outliers rejected,
+    and the list conversion overhead (extract_ns, convert_ns) kept apart from the sort time
+benchmark(algorithms=None, sizes=[1000, 10000, 100000], distribution="uniform", runs=10, seed=None,
+    dtype="float32", warmup=1) -> times the algorithms (all that support dtype by default) on data
+    generated in Rust, only the sorts are timed, and returns a dict of equal length columns: Method,
+    Algorithm, Size, Mean, SD, Median, Min, Max (μs), Outliers, Type and Dist
+generate(distribution, n, seed=None, dtype="float32") -> an array.array of n elements of the same
+    seeded distribution as the Rust benches (RUSORTS_SEED or the default seed if seed is None),
+    numpy.asarray wraps it without copying, distributions() lists the names
+sort_async(v, algorithm="hoare_block") -> sorts on a background thread and returns a SortFuture,
+    `done()`, `result(timeout=None)` or `await` it to get the (sorted, ns) tuple
+Every function releases the GIL while sorting, so other Python threads keep running
+"""
+"#)?;
+    Ok(())
+}
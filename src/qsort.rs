@@ -11,6 +11,7 @@ use std::simd::{cmp::SimdPartialOrd, *};
 use std::cmp::Ordering;
 use std::{ptr, mem, cmp};
 
+use crate::probe;
 use crate::util::*;
 
 #[cfg(debug_assertions)]
@@ -19,12 +20,29 @@ const DEBUG_INSERTION_SORT_THRESHOLD: usize = 9;
 const RELEASE_INSERTION_SORT_THRESHOLD: usize = 27;
 
 
+/// Report a branch on `$cond` to the branch simulator of `$t` elements, only
+/// branches whose outcome depends on comparisons are reported. The classify
+/// loops of the block partitions are branch free and report nothing
+macro_rules! branch {
+    ($t: ty, $cond: expr) => {
+        probe::branch::<$t>(line!(), $cond)
+    };
+}
+
+/// Bitwise copy of a pivot that must never be dropped, the original stays in
+/// the slice. Without the `ManuallyDrop` types owning heap memory (`String`)
+/// would be freed twice
+#[inline(always)]
+unsafe fn read_pivot<T>(src: *const T) -> mem::ManuallyDrop<T> {
+    mem::ManuallyDrop::new(probe::read(src))
+}
+
 #[inline]
 fn insertion_sort<T: Ord>(arr: &mut [T], left: usize, right: usize) {
 	for i in (left + 1)..(right + 1) {
 		let mut j = i;
-		while j > left && arr[j].cmp(&arr[j - 1]) == Ordering::Less {
-			arr.swap(j, j - 1);
+		while j > left && branch!(T, arr[j].cmp(&arr[j - 1]) == Ordering::Less) {
+			probe::swap(arr, j, j - 1);
 			j = j - 1;
 		}
 	}
@@ -35,7 +53,7 @@ fn partial_insertion_sort<T: PartialOrd>(arr: &mut [T], left: usize, right: usiz
     for i in (left + 1)..(right + 1) {
         let mut j = i;
         while j > left && arr[j] < arr[j - 1] {
-            arr.swap(j, j - 1);
+            probe::swap(arr, j, j - 1);
             j = j - 1;
         }
     }
@@ -45,13 +63,13 @@ macro_rules! conditional_sort {
     (debug, $arr: expr) => {
         #[cfg(debug_assertions)]
         if $arr.len() < DEBUG_INSERTION_SORT_THRESHOLD {
-            return insertion_sort($arr, 0, $arr.len() - 1);
+            return insertion_sort($arr, 0, $arr.len().saturating_sub(1));
         }
     };
     (release, $arr: expr) => {
         #[cfg(not(debug_assertions))]
         if $arr.len() < RELEASE_INSERTION_SORT_THRESHOLD {
-            return insertion_sort($arr, 0, $arr.len() - 1);
+            return insertion_sort($arr, 0, $arr.len().saturating_sub(1));
         }
     };
 }
@@ -60,13 +78,13 @@ macro_rules! conditional_partial_sort {
     (debug, $arr: expr) => {
         #[cfg(debug_assertions)]
         if $arr.len() < DEBUG_INSERTION_SORT_THRESHOLD {
-            return partial_insertion_sort($arr, 0, $arr.len() - 1);
+            return partial_insertion_sort($arr, 0, $arr.len().saturating_sub(1));
         }
     };
     (release, $arr: expr) => {
         #[cfg(not(debug_assertions))]
         if $arr.len() < RELEASE_INSERTION_SORT_THRESHOLD {
-            return partial_insertion_sort($arr, 0, $arr.len() - 1);
+            return partial_insertion_sort($arr, 0, $arr.len().saturating_sub(1));
         }
     };
 }
@@ -77,15 +95,15 @@ pub fn quick_sort_lomuto_partition<T: Ord>(mut arr: &mut [T]) {
             conditional_sort!(debug, arr);
             conditional_sort!(release, arr);
             let (left, right) = (0, arr.len() - 1);
-            let pivot = ptr::read(arr.get_unchecked(right));
+            let pivot = read_pivot(arr.get_unchecked(right));
             let mut i = left;
             for j in left..right {
-                if arr[j].cmp(&pivot) == Ordering::Less || arr[j].cmp(&pivot) == Ordering::Equal {
-                    arr.swap_unchecked(i, j);
+                if branch!(T, arr[j].cmp(&pivot) == Ordering::Less || arr[j].cmp(&pivot) == Ordering::Equal) {
+                    probe::swap_unchecked(arr, i, j);
                     i += 1;
                 }
             }
-            arr.swap_unchecked(i, right);
+            probe::swap_unchecked(arr, i, right);
             let (left, right) = arr.split_at_mut(i);
             if left.len() > right.len() {
                 quick_sort_lomuto_partition(right);
@@ -107,7 +125,7 @@ pub fn quick_sort_lomuto_partition_block<T: Ord>(mut arr: &mut [T]) {
             conditional_sort!(debug, arr);
             conditional_sort!(release, arr);
             let (left, right) = (0, arr.len() - 1);
-            let pivot = ptr::read(arr.get_unchecked(right));
+            let pivot = read_pivot(arr.get_unchecked(right));
             let (mut i, mut j) = (left, left);
             let mut num = 0;
             while j < right {
@@ -117,13 +135,13 @@ pub fn quick_sort_lomuto_partition_block<T: Ord>(mut arr: &mut [T]) {
                     num += (arr[j + k].cmp(&pivot) == Ordering::Less) as usize;
                 }
                 for k in 0..num {
-                    arr.swap_unchecked(i, j + offsets_t[k] as usize);
+                    probe::swap_unchecked(arr, i, j + offsets_t[k] as usize);
                     i += 1;
                 }
                 num = 0;
                 j += block_t;
             }
-            arr.swap_unchecked(i, right);
+            probe::swap_unchecked(arr, i, right);
             let (left, right) = arr.split_at_mut(i);
             let (pivot, right) = right.split_at_mut(1);
             let pivot = &pivot[0];
@@ -148,10 +166,10 @@ pub fn double_pivot_quicksort_lomuto_partition_block<T: Ord>(mut arr: &mut [T])
             conditional_sort!(debug, arr);
             conditional_sort!(release, arr);
             let (left, right) = (0, arr.len() - 1);
-            if arr.get_unchecked(left).cmp(arr.get_unchecked(right)) == Ordering::Greater {
-                arr.swap_unchecked(left, right);
+            if branch!(T, arr.get_unchecked(left).cmp(arr.get_unchecked(right)) == Ordering::Greater) {
+                probe::swap_unchecked(arr, left, right);
             }
-            let (pivot1, pivot2) = (ptr::read(arr.get_unchecked(left)), ptr::read(arr.get_unchecked(right)));
+            let (pivot1, pivot2) = (read_pivot(arr.get_unchecked(left)), read_pivot(arr.get_unchecked(right)));
         
             let (mut i, mut j, mut k) = (left + 1, left + 1, left + 1);
             let (mut num_p1, mut num_p2) = (0, 0);
@@ -162,7 +180,7 @@ pub fn double_pivot_quicksort_lomuto_partition_block<T: Ord>(mut arr: &mut [T])
                     num_p2 += (arr[k + l].cmp(&pivot2) == Ordering::Less) as usize;
                 }
                 for l in 0..num_p2 {
-                    arr.swap_unchecked(j + l, k + offsets_t[l] as usize);
+                    probe::swap_unchecked(arr, j + l, k + offsets_t[l] as usize);
                 }
                 k += block_t;
                 for l in 0..num_p2 {
@@ -170,15 +188,15 @@ pub fn double_pivot_quicksort_lomuto_partition_block<T: Ord>(mut arr: &mut [T])
                     num_p1 += (arr[j + l].cmp(&pivot1) == Ordering::Less) as usize;
                 }
                 for l in 0..num_p1 {
-                    arr.swap_unchecked(i, j + offsets_t[l] as usize);
+                    probe::swap_unchecked(arr, i, j + offsets_t[l] as usize);
                     i += 1;
                 }
                 j += num_p2;
                 num_p1 = 0;
                 num_p2 = 0;
             }
-            arr.swap_unchecked(i - 1, left);
-            arr.swap_unchecked(j, right);
+            probe::swap_unchecked(arr, i - 1, left);
+            probe::swap_unchecked(arr, j, right);
             let (left, right) = arr.split_at_mut(i - 1);
             let (pivot1, right) = right.split_at_mut(1);
             let _pivot1 = &pivot1[0];
@@ -213,10 +231,10 @@ pub fn double_pivot_quicksort_new_partition_block<T: Ord>(mut arr: &mut [T]) {
             conditional_sort!(debug, arr);
             conditional_sort!(release, arr);
             let (left, right) = (0, arr.len() - 1);
-            if arr.get_unchecked(left).cmp(arr.get_unchecked(right)) == Ordering::Greater {
-                arr.swap_unchecked(left, right);
+            if branch!(T, arr.get_unchecked(left).cmp(arr.get_unchecked(right)) == Ordering::Greater) {
+                probe::swap_unchecked(arr, left, right);
             }
-            let (pivot1, pivot2) = (ptr::read(arr.get_unchecked(left)), ptr::read(arr.get_unchecked(right)));
+            let (pivot1, pivot2) = (read_pivot(arr.get_unchecked(left)), read_pivot(arr.get_unchecked(right)));
             // dbg!(&pivot1, &pivot2);
             let (mut i, mut j, mut k) = (left + 1, left + 1, left + 1);
             let (mut num_p1, mut num_p2) = (0, 0);
@@ -238,12 +256,12 @@ pub fn double_pivot_quicksort_new_partition_block<T: Ord>(mut arr: &mut [T]) {
                         break;
                     }
                     let idx_off = cmp::min(offsets[idx_p1], offsets[block_t - 1 - idx_p2]);                    
-                    if idx_off == offsets[idx_p1] {
+                    if branch!(T, idx_off == offsets[idx_p1]) {
                         rotate3(arr.as_mut_ptr(), [k + idx_off as usize, j, i]);
                         i += 1;
                         idx_p1 += 1;
                     } else {
-                        arr.swap_unchecked(k + idx_off as usize, j);
+                        probe::swap_unchecked(arr, k + idx_off as usize, j);
                         idx_p2 += 1;
                     }
                     j += 1;
@@ -258,7 +276,7 @@ pub fn double_pivot_quicksort_new_partition_block<T: Ord>(mut arr: &mut [T]) {
                 }
                 if idx_p2 < num_p2 {
                     for idx_off in idx_p2..num_p2 {
-                        arr.swap_unchecked(k + offsets[block_t - 1 - idx_off] as usize, j);
+                        probe::swap_unchecked(arr, k + offsets[block_t - 1 - idx_off] as usize, j);
                         j += 1;
                     }
                 }
@@ -266,8 +284,8 @@ pub fn double_pivot_quicksort_new_partition_block<T: Ord>(mut arr: &mut [T]) {
                 num_p1 = 0;
                 num_p2 = 0;
             }
-            arr.swap_unchecked(i - 1, left);
-            arr.swap_unchecked(j, right);
+            probe::swap_unchecked(arr, i - 1, left);
+            probe::swap_unchecked(arr, j, right);
             let (left, right) = arr.split_at_mut(i - 1);
             let (pivot1, right) = right.split_at_mut(1);
             let _pivot1 = &pivot1[0];
@@ -300,22 +318,22 @@ pub fn quick_sort_hoare_partition<T: Ord>(mut arr: &mut [T]) {
             conditional_sort!(release, arr);
             let mut i = -1;
             let mut j = arr.len() as isize;
-            let pivot = ptr::read(arr.get_unchecked(0));
+            let pivot = read_pivot(arr.get_unchecked(0));
             loop {
                 i += 1;
-                while arr[i as usize].cmp(&pivot) == Ordering::Less {
+                while branch!(T, arr[i as usize].cmp(&pivot) == Ordering::Less) {
                     i += 1;
                 }
         
                 j -= 1;
-                while arr[j as usize].cmp(&pivot) == Ordering::Greater {
+                while branch!(T, arr[j as usize].cmp(&pivot) == Ordering::Greater) {
                     j -= 1;
                 }
         
                 if i >= j {
                     break;
                 }
-                arr.swap_unchecked(i as usize, j as usize);
+                probe::swap_unchecked(arr, i as usize, j as usize);
             }
 
             // arr.swap_unchecked(0, j as usize);
@@ -406,23 +424,23 @@ fn partition_in_blocks<T, F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
 
         let count = cmp::min(width(start_l, end_l), width(start_r, end_r));
 
-        if count > 0 {
+        if branch!(T, count > 0) {
             macro_rules! left { () => { l.offset(*start_l as isize) } }
             macro_rules! right { () => { r.offset(-(*start_r as isize) - 1) } }
 
             // cyclic swap
             unsafe {
-                let tmp = ptr::read(left!());
-                ptr::copy_nonoverlapping(right!(), left!(), 1);
+                let tmp = probe::read(left!());
+                probe::copy_nonoverlapping(right!(), left!());
 
                 for _ in 1..count {
                     start_l = start_l.offset(1);
-                    ptr::copy_nonoverlapping(left!(), right!(), 1);
+                    probe::copy_nonoverlapping(left!(), right!());
                     start_r = start_r.offset(1);
-                    ptr::copy_nonoverlapping(right!(), left!(), 1);
+                    probe::copy_nonoverlapping(right!(), left!());
                 }
 
-                ptr::copy_nonoverlapping(&tmp, right!(), 1);
+                probe::copy_nonoverlapping(&tmp, right!());
                 mem::forget(tmp);
                 start_l = start_l.offset(1);
                 start_r = start_r.offset(1);
@@ -447,7 +465,7 @@ fn partition_in_blocks<T, F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
         while start_l < end_l {
             unsafe {
                 end_l = end_l.offset(-1);
-                ptr::swap(l.offset(*end_l as isize), r.offset(-1));
+                probe::swap_ptr(l.offset(*end_l as isize), r.offset(-1));
                 r = r.offset(-1);
             }
         }
@@ -457,7 +475,7 @@ fn partition_in_blocks<T, F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
         while start_r < end_r {
             unsafe {
                 end_r = end_r.offset(-1);
-                ptr::swap(l, r.offset(-(*end_r as isize) - 1));
+                probe::swap_ptr(l, r.offset(-(*end_r as isize) - 1));
                 l = l.offset(1);
             }
         }
@@ -477,21 +495,22 @@ pub fn quick_sort_hoare_partition_block<T: Ord>(mut arr: &mut [T]) {
             conditional_sort!(debug, arr);
             conditional_sort!(release, arr);
             let (mid, _was_partitioned) = {
-                let (pivot, arr) = arr.split_at_mut(1);
-                let pivot = &mut pivot[0];
+                let pivot = read_pivot(arr.get_unchecked(0));
+                let pivot: &T = &pivot;
+                let arr = &mut arr[1..];
                 let mut l = 0;
                 let mut r = arr.len();
-                while l < r && is_less(arr.get_unchecked(l), pivot) {
+                while l < r && branch!(T, is_less(arr.get_unchecked(l), pivot)) {
                     l += 1;
                 }
         
-                while l < r && is_less(pivot, arr.get_unchecked(r - 1)) {
+                while l < r && branch!(T, is_less(pivot, arr.get_unchecked(r - 1))) {
                     r -= 1;
                 }
     
                 (l + partition_in_blocks(&mut arr[l..r], pivot, is_less), l >= r)
             };
-            arr.swap_unchecked(0, mid);
+            probe::swap_unchecked(arr, 0, mid);
     
             let (left, right) = arr.split_at_mut(mid);
             let (_pivot, right) = right.split_at_mut(1);
@@ -517,15 +536,15 @@ pub fn double_pivot_quicksort<T: Ord>(arr: &mut [T]) {
 	let (left, right) = (0, arr.len() - 1);
 
 	unsafe {
-		// pivots
-		let pivot1 : *mut T = &mut arr[left];
-		let pivot2 : *mut T = &mut arr[right];
-		
 		// swap pivots if p1 > p2
-		if (&*pivot1).cmp(&*pivot2) == Ordering::Greater {
-			arr.swap_unchecked(left, right);
+		if branch!(T, arr[left].cmp(&arr[right]) == Ordering::Greater) {
+			probe::swap_unchecked(arr, left, right);
 		}
 
+		// pivots
+		let (pivot1, pivot2) = (read_pivot(arr.get_unchecked(left)), read_pivot(arr.get_unchecked(right)));
+		let (pivot1, pivot2): (&T, &T) = (&pivot1, &pivot2);
+
 		// partition indexes
 		let mut less = left + 1;
 		let mut greater = right - 1;
@@ -533,31 +552,31 @@ pub fn double_pivot_quicksort<T: Ord>(arr: &mut [T]) {
 		// sorting
 		let mut k = less;
 		while k <= greater {
-			if arr[k].cmp(&*pivot1) == Ordering::Less {
-                arr.swap_unchecked(k, less);
+			if branch!(T, arr[k].cmp(pivot1) == Ordering::Less) {
+                probe::swap_unchecked(arr, k, less);
                 less = less + 1;
             }
-			else if arr[k].cmp(&*pivot2) == Ordering::Greater {
+			else if branch!(T, arr[k].cmp(pivot2) == Ordering::Greater) {
                 // find the rightmost element less than pivot2
-                while k < greater && arr[greater].cmp(&*pivot2) == Ordering::Greater {
+                while k < greater && branch!(T, arr[greater].cmp(pivot2) == Ordering::Greater) {
                     greater = greater - 1;
                 }
                 // swap it with arr[k]
-                arr.swap_unchecked(k, greater);
+                probe::swap_unchecked(arr, k, greater);
                 greater = greater - 1;
 
                 // if the swapped element is less than pivot1
                 // then swap it with arr[less]
-                if arr[k].cmp(&*pivot1) == Ordering::Less {
-                    arr.swap_unchecked(k, less);
+                if branch!(T, arr[k].cmp(pivot1) == Ordering::Less) {
+                    probe::swap_unchecked(arr, k, less);
                     less = less + 1;
                 }
             }
 			k = k + 1;
 		}
 		
-		arr.swap_unchecked(less - 1, left);
-		arr.swap_unchecked(greater + 1, right);
+		probe::swap_unchecked(arr, less - 1, left);
+		probe::swap_unchecked(arr, greater + 1, right);
 
 		if less > left + 2 {
 			double_pivot_quicksort(&mut arr[left..less - 1]);
@@ -567,7 +586,7 @@ pub fn double_pivot_quicksort<T: Ord>(arr: &mut [T]) {
 			double_pivot_quicksort(&mut arr[greater + 2..=right]);
 		}
 
-		if less < greater && (&*pivot1).cmp(&*pivot2) == Ordering::Less { // some elements are equal to pivot1 or pivot2
+		if less < greater && branch!(T, pivot1.cmp(pivot2) == Ordering::Less) { // some elements are equal to pivot1 or pivot2
 			double_pivot_quicksort(&mut arr[less..=greater]);
 		}
 	}
@@ -584,41 +603,41 @@ pub fn triple_pivot_quicksort<T: Ord>(arr: &mut [T]) {
 		let p2: *mut T = &mut arr[left + 1];
 		let p3: *mut T = &mut arr[right];
 	
-		if (&*p1).cmp(&*p2) == Ordering::Greater {
-			arr.swap_unchecked(left, left + 1);
+		if branch!(T, (&*p1).cmp(&*p2) == Ordering::Greater) {
+			probe::swap_unchecked(arr, left, left + 1);
 		}
-		if (&*p2).cmp(&*p3) == Ordering::Greater {
-			arr.swap_unchecked(left + 1, right);
+		if branch!(T, (&*p2).cmp(&*p3) == Ordering::Greater) {
+			probe::swap_unchecked(arr, left + 1, right);
 		}
-		if (&*p1).cmp(&*p2) == Ordering::Greater { 
-			arr.swap_unchecked(left, left + 1);
+		if branch!(T, (&*p1).cmp(&*p2) == Ordering::Greater) { 
+			probe::swap_unchecked(arr, left, left + 1);
 		}
 
 		let (mut i, mut j, mut k, mut l) = (left + 2, left + 2, right - 1, right - 1);
-		let (p1, p2, p3) = (ptr::read(p1), ptr::read(p2), ptr::read(p3));
+		let (p1, p2, p3) = (read_pivot(p1), read_pivot(p2), read_pivot(p3));
 		while j <= k {
 			// j moves right until arr[j] >= p2
-			while arr[j].cmp(&p2) == Ordering::Less {
+			while branch!(T, arr[j].cmp(&p2) == Ordering::Less) {
 				// arr[<i] -> elements that are less than p1, arr[i] is not less than p1
-				if arr[j].cmp(&p1) == Ordering::Less {
-					arr.swap_unchecked(i, j);
+				if branch!(T, arr[j].cmp(&p1) == Ordering::Less) {
+					probe::swap_unchecked(arr, i, j);
 					i += 1;
 				}
 				j += 1;
 			}
 			// k moves left until arr[k] <= p2
-			while arr[k].cmp(&p2) == Ordering::Greater {
+			while branch!(T, arr[k].cmp(&p2) == Ordering::Greater) {
 				// arr[>l] -> elements that are greater than p3, arr[l] is not greater than p3
-				if arr[k].cmp(&p3) == Ordering::Greater {
-					arr.swap_unchecked(k, l);
+				if branch!(T, arr[k].cmp(&p3) == Ordering::Greater) {
+					probe::swap_unchecked(arr, k, l);
 					l -= 1;
 				}
 				k -= 1;
 			}
 			// if j is still less than k
 			if j <= k {
-				if arr[j].cmp(&p3) == Ordering::Greater {
-					if arr[k].cmp(&p1) == Ordering::Less {
+				if branch!(T, arr[j].cmp(&p3) == Ordering::Greater) {
+					if branch!(T, arr[k].cmp(&p1) == Ordering::Less) {
 						// if arr[j] > p3 and arr[k] < p1, 
 						// rotate arr[j] to k and arr[k] to i because arr[<i] < p1
 						rotate3(arr.as_mut_ptr(), [j, i, k]);
@@ -626,19 +645,19 @@ pub fn triple_pivot_quicksort<T: Ord>(arr: &mut [T]) {
 					} else {
 						// if arr[j] > p3 and arr[k] >= p1,
 						// simply swap arr[j] and arr[k]
-						arr.swap_unchecked(j, k);
+						probe::swap_unchecked(arr, j, k);
 					}
 					// at this moment arr[k] must be greater than p3
 					// swap it with arr[l] to move it to the right
-					arr.swap_unchecked(k, l);
+					probe::swap_unchecked(arr, k, l);
 					l -= 1;
 				} else { 
 					// if arr[j] <= p3, we do the same logic as above
-					if arr[k].cmp(&p1) == Ordering::Less {
+					if branch!(T, arr[k].cmp(&p1) == Ordering::Less) {
 						rotate3(arr.as_mut_ptr(), [j, i, k]);
 						i += 1;
 					} else {
-						arr.swap_unchecked(j, k);
+						probe::swap_unchecked(arr, j, k);
 					}
 					// at this moment arr[j] must be less than or equal p1
 					// arr[k] must be less than or equal p3
@@ -655,13 +674,13 @@ pub fn triple_pivot_quicksort<T: Ord>(arr: &mut [T]) {
 		// at this point arr[<=i] < p1, arr[i..=j] >= p1 and <= p2, arr[k..=l] >= p2 and <= p3, arr[>=l] > p3 (j == k)
 		// move p2 from arr[left + 1] to vacant position arr[j] (in the middle) 
 		rotate3(arr.as_mut_ptr(), [left + 1, i, j]);
-		// arr.swap_unchecked(left + 1, i);
-		// arr.swap_unchecked(i, j);
+		// probe::swap_unchecked(arr, left + 1, i);
+		// probe::swap_unchecked(arr, i, j);
 
 		i -= 1;
 
-		arr.swap_unchecked(left, i);
-		arr.swap_unchecked(right, l);
+		probe::swap_unchecked(arr, left, i);
+		probe::swap_unchecked(arr, right, l);
 		if left + 1 < i {
 			triple_pivot_quicksort(&mut arr[left..i]);
 		}
@@ -689,38 +708,38 @@ pub fn quad_pivot_quicksort<T: Ord>(arr: &mut [T]) {
 		let p3: *mut T = &mut arr[right - 1];
         let p4: *mut T = &mut arr[right];
 
-        if (&*p1).cmp(&*p2) == Ordering::Greater {
-            arr.swap_unchecked(left, left + 1);
+        if branch!(T, (&*p1).cmp(&*p2) == Ordering::Greater) {
+            probe::swap_unchecked(arr, left, left + 1);
         }
-        if (&*p2).cmp(&*p3) == Ordering::Greater {
-            arr.swap_unchecked(left + 1, right - 1);
+        if branch!(T, (&*p2).cmp(&*p3) == Ordering::Greater) {
+            probe::swap_unchecked(arr, left + 1, right - 1);
         }
-        if (&*p3).cmp(&*p4) == Ordering::Greater {
-            arr.swap_unchecked(right - 1, right);
+        if branch!(T, (&*p3).cmp(&*p4) == Ordering::Greater) {
+            probe::swap_unchecked(arr, right - 1, right);
         }
-        if (&*p1).cmp(&*p2) == Ordering::Greater {
-            arr.swap_unchecked(left, left + 1);
+        if branch!(T, (&*p1).cmp(&*p2) == Ordering::Greater) {
+            probe::swap_unchecked(arr, left, left + 1);
         }
-        if (&*p2).cmp(&*p3) == Ordering::Greater {
-            arr.swap_unchecked(left + 1, right - 1);
+        if branch!(T, (&*p2).cmp(&*p3) == Ordering::Greater) {
+            probe::swap_unchecked(arr, left + 1, right - 1);
         }
-        if (&*p1).cmp(&*p2) == Ordering::Greater {
-            arr.swap_unchecked(left, left + 1);
+        if branch!(T, (&*p1).cmp(&*p2) == Ordering::Greater) {
+            probe::swap_unchecked(arr, left, left + 1);
         }
 
         let (mut i, mut j, mut k, mut l, mut m) = (left + 2, left + 2, left + 2, right - 2, right - 2);
-		let (p1, p2, p3, p4) = (ptr::read(p1), ptr::read(p2), ptr::read(p3), ptr::read(p4));
+		let (p1, p2, p3, p4) = (read_pivot(p1), read_pivot(p2), read_pivot(p3), read_pivot(p4));
         
         while k <= l {
             //        | i              | j              | k
             // | < p1 | >= p1 and < p2 | >= p2 and < p3 | unknown
-            while arr[k].cmp(&p3) == Ordering::Less {
-                if arr[k].cmp(&p1) == Ordering::Less {
+            while branch!(T, arr[k].cmp(&p3) == Ordering::Less) {
+                if branch!(T, arr[k].cmp(&p1) == Ordering::Less) {
                     rotate3(arr.as_mut_ptr(), [k, j, i]);
                     i += 1;
                     j += 1;
-                } else if arr[k].cmp(&p2) == Ordering::Less {
-                    arr.swap_unchecked(k, j);
+                } else if branch!(T, arr[k].cmp(&p2) == Ordering::Less) {
+                    probe::swap_unchecked(arr, k, j);
                     j += 1;
                 }
                 k += 1;
@@ -728,32 +747,32 @@ pub fn quad_pivot_quicksort<T: Ord>(arr: &mut [T]) {
 
             //       l |              m |      |               
             // unknown | >= p3 and < p4 | > p4 |
-            while arr[l].cmp(&p3) == Ordering::Greater {
-                if arr[l].cmp(&p4) == Ordering::Greater {
-                    arr.swap_unchecked(l, m);
+            while branch!(T, arr[l].cmp(&p3) == Ordering::Greater) {
+                if branch!(T, arr[l].cmp(&p4) == Ordering::Greater) {
+                    probe::swap_unchecked(arr, l, m);
                     m -= 1;
                 }
                 l -= 1;
             }
 
             if k <= l {
-                if arr[k].cmp(&p4) == Ordering::Less {
+                if branch!(T, arr[k].cmp(&p4) == Ordering::Less) {
                     // arr[k] > p3, arr[l] < p3
-                    if arr[l].cmp(&p1) == Ordering::Less {
+                    if branch!(T, arr[l].cmp(&p1) == Ordering::Less) {
                         rotate4(arr.as_mut_ptr(), [k, j, i, l]);
                         i += 1;
                         j += 1;
-                    } else if arr[l].cmp(&p2) == Ordering::Less {
+                    } else if branch!(T, arr[l].cmp(&p2) == Ordering::Less) {
                         rotate3(arr.as_mut_ptr(), [k, j, l]);
                         j += 1;
                     } else {
-                        arr.swap_unchecked(k, l);
+                        probe::swap_unchecked(arr, k, l);
                     }
                 } else {
                     // arr[k] > p4, arr[l] < p3
-                    if arr[l].cmp(&p2) == Ordering::Greater { // arr[l] goes to (p2, p3), increase k
+                    if branch!(T, arr[l].cmp(&p2) == Ordering::Greater) { // arr[l] goes to (p2, p3), increase k
                         rotate3(arr.as_mut_ptr(), [k, l, m]);
-                    } else if arr[l].cmp(&p1) == Ordering::Greater { // arr[l] goes to (p1, p2), increase j and k
+                    } else if branch!(T, arr[l].cmp(&p1) == Ordering::Greater) { // arr[l] goes to (p1, p2), increase j and k
                         rotate4(arr.as_mut_ptr(), [k, j, l, m]);
                         j += 1;
                     } else { // arr[l] goes to leftmost side
@@ -780,11 +799,11 @@ pub fn quad_pivot_quicksort<T: Ord>(arr: &mut [T]) {
         // anyway, I leave the rotate_n macro for you to try out in `src/util.rs`
         rotate3(arr.as_mut_ptr(), [left + 1, i, j]);
         i -= 1;
-        arr.swap_unchecked(left, i);
+        probe::swap_unchecked(arr, left, i);
         
         rotate3(arr.as_mut_ptr(), [right - 1, m, l]);
         m += 1;
-        arr.swap_unchecked(right, m);
+        probe::swap_unchecked(arr, right, m);
 
         if left + 1 < i {
             quad_pivot_quicksort(&mut arr[left..i]);
@@ -806,30 +825,50 @@ pub fn quad_pivot_quicksort<T: Ord>(arr: &mut [T]) {
 }
 
 
-use once_cell::unsync::Lazy;
-static mut ARENA: Lazy<Vec<Vec<f32>>> = Lazy::new(||
-    (0..9).map(|_| Vec::with_capacity(10_000_000)).collect::<Vec<_>>()
-);
+use std::cell::UnsafeCell;
 
+const ARENA_BUCKETS: usize = 9;
 
+thread_local! {
+    // one arena per thread, so the SIMD sorts can run on several threads at once. The
+    // buckets grow with the slices sorted on the thread and are reused by the next sort
+    static ARENA: UnsafeCell<Vec<Vec<f32>>> = const { UnsafeCell::new(Vec::new()) };
+}
+
+/// # Safety
+///
+/// the arena of the current thread, the reference must not outlive the
+/// thread or be held across another call to `arena`
 #[inline]
-fn thread_local_arena_reset() {
+unsafe fn arena() -> &'static mut Vec<Vec<f32>> {
+    ARENA.with(|arena| &mut *arena.get())
+}
+
+
+/// empty every bucket, with room for an even split of `len` elements
+#[inline]
+fn thread_local_arena_reset(len: usize) {
     unsafe {
-        ARENA.iter_mut().for_each(|bucket| bucket.clear());
+        let arena = arena();
+        arena.resize_with(ARENA_BUCKETS, Vec::new);
+        arena.iter_mut().for_each(|bucket| {
+            bucket.clear();
+            bucket.reserve(len / ARENA_BUCKETS);
+        });
     }
 }
 
 #[inline]
 fn thread_local_arena_push(i: usize, n: f32) {
     unsafe {
-        ARENA[i].push(n);
+        arena()[i].push(n);
     };
 }
 
 #[inline]
 fn thread_local_arena_clear_lane(i: usize) {
     unsafe {
-        ARENA[i].clear();
+        arena()[i].clear();
     }
 }
 
@@ -851,7 +890,7 @@ macro_rules! impl_4n_pivot_qsort {
 
             // let mut arena: Vec<Vec<$data_type>> = vec![Vec::with_capacity(arr.len()); $n + 1];
             unsafe {
-                thread_local_arena_reset();
+                thread_local_arena_reset(arr.len());
                 for &mut x in arr.iter_mut() {
                     let x_vec = <$simd_type>::splat(x);
                     let mask = x_vec.simd_ge(pivot_vec);
@@ -874,7 +913,7 @@ macro_rules! impl_4n_pivot_qsort {
 
                 let mut arr_ptr = arr.as_mut_ptr();
                 for bucket in 0..=$n {
-                    let bucket_cur = ARENA.get_unchecked(bucket);
+                    let bucket_cur = arena().get_unchecked(bucket);
                     let bucket_ptr = bucket_cur.as_ptr();
                     let bucket_len = bucket_cur.len();
                     bucket_sizes[bucket] = bucket_len;
@@ -884,6 +923,10 @@ macro_rules! impl_4n_pivot_qsort {
                 };
             }
 
+            // the pivots could not split the array (all duplicates), recursing would never end
+            if bucket_sizes.contains(&arr.len()) {
+                return arr.sort_unstable_by(f32::total_cmp);
+            }
             $func_name(&mut arr[0..bucket_sizes[0]]);
             for i in 1..=$n {
                 $func_name(&mut arr[bucket_sizes[..i].iter().sum::<usize>()..bucket_sizes[..i + 1].iter().sum::<usize>()]);
@@ -915,9 +958,9 @@ pub fn quadro_pivot_quicksort_2(arr: &mut [f32]) {
     // the size of arr is guaranteed to be a multiple of 8 in the first step, but not for the remainings
     
     let mut a = arr.to_vec();
-    thread_local_arena_reset();
+    thread_local_arena_reset(arr.len());
     for i in 0..n_pivots {
-        if i > 0 { a = unsafe { ARENA[i].clone() }; }
+        if i > 0 { a = unsafe { arena()[i].clone() }; }
         // dbg!(&a.len());
         thread_local_arena_clear_lane(i);
         let pivot_vec = f32x8::splat(pivots[i]);
@@ -948,14 +991,14 @@ pub fn quadro_pivot_quicksort_2(arr: &mut [f32]) {
         if i < n_pivots - 1 { a.clear(); }
         // dbg!(&a.len(), bucket_sizes);
     }
-    bucket_sizes[n_pivots] = unsafe { ARENA[n_pivots].len() };
+    bucket_sizes[n_pivots] = unsafe { arena()[n_pivots].len() };
     // memcpy from buckets back to arr
     // dbg!(bucket_sizes);
     unsafe {
         let mut arr_ptr = arr.as_mut_ptr();
         for i in 0..=n_pivots {
             // assert!(arr_ptr.align_offset(16) == 0);
-            let bucket_ptr = ARENA[i].as_ptr();
+            let bucket_ptr = arena()[i].as_ptr();
             // assert!(bucket_ptr.align_offset(16) == 0);
             let bucket_len = bucket_sizes[i];
             // dbg!(bucket_len);
@@ -964,6 +1007,10 @@ pub fn quadro_pivot_quicksort_2(arr: &mut [f32]) {
         };
     }
     // bg!(n, bucket_sizes);
+    // the pivots could not split the array (all duplicates), recursing would never end
+    if bucket_sizes.contains(&arr.len()) {
+        return arr.sort_unstable_by(f32::total_cmp);
+    }
     quadro_pivot_quicksort_2(&mut arr[0..bucket_sizes[0]]);
     quadro_pivot_quicksort_2(&mut arr[bucket_sizes[0]..bucket_sizes[0] + bucket_sizes[1]]);
     quadro_pivot_quicksort_2(&mut arr[bucket_sizes[0] + bucket_sizes[1]..bucket_sizes[0] + bucket_sizes[1] + bucket_sizes[2]]);
@@ -988,7 +1035,7 @@ macro_rules! impl_non_4n_pivot_qsort {
         
             let arr_chunks = arr.chunks_exact($pivot_repeat_times);
             let r = arr_chunks.remainder().clone();
-            thread_local_arena_reset();
+            thread_local_arena_reset(arr.len());
 
             let filled = pivots.repeat($pivot_repeat_times);
             let pivots_vecs = filled.chunks($simd_len); // construct pivots_vecs
@@ -1042,7 +1089,7 @@ macro_rules! impl_non_4n_pivot_qsort {
             unsafe {
                 let mut arr_ptr = arr.as_mut_ptr();
                 for i in 0..=$n {
-                    let bucket = ARENA.get_unchecked(i);
+                    let bucket = arena().get_unchecked(i);
                     let bucket_ptr = bucket.as_ptr();
                     let bucket_len = bucket.len();
                     bucket_sizes[i] = bucket_len;
@@ -1050,6 +1097,10 @@ macro_rules! impl_non_4n_pivot_qsort {
                     arr_ptr = arr_ptr.add(bucket_len);
                 };
             }
+            // the pivots could not split the array (all duplicates), recursing would never end
+            if bucket_sizes.contains(&arr.len()) {
+                return arr.sort_unstable_by(f32::total_cmp);
+            }
             $func_name(&mut arr[0..bucket_sizes[0]]);
             for i in 1..=$n {
                 $func_name(&mut arr[bucket_sizes[..i].iter().sum::<usize>()..bucket_sizes[..i + 1].iter().sum::<usize>()]);
@@ -0,0 +1,224 @@
+use core::cmp::Reverse;
+use core::fmt;
+use core::str::FromStr;
+
+use crumsort::ParCrumSort;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+use crate::qsort::*;
+use crate::ty::{Bytes, DoubleOrd, FloatOrd, Keyed, Record, Tuple};
+
+macro_rules! impl_algorithms {
+    ($($variant:ident => $name:expr, $label:expr, parallel: $parallel:expr, simd: $simd:expr;)*) => {
+        /// Every sorting algorithm of the crate, so benches and bindings can pick one by name
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Algorithm {
+            $($variant,)*
+        }
+
+        impl Algorithm {
+            pub const ALL: &'static [Algorithm] = &[$(Algorithm::$variant,)*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Algorithm::$variant => $name,)*
+                }
+            }
+
+            /// name used in the `Method` column of the result CSVs
+            pub fn label(self) -> &'static str {
+                match self {
+                    $(Algorithm::$variant => $label,)*
+                }
+            }
+
+            /// runs on the rayon thread pool
+            pub fn is_parallel(self) -> bool {
+                match self {
+                    $(Algorithm::$variant => $parallel,)*
+                }
+            }
+
+            /// f32 only, partitions into the SIMD arena of the thread it runs on, each thread has its own
+            pub fn is_simd(self) -> bool {
+                match self {
+                    $(Algorithm::$variant => $simd,)*
+                }
+            }
+        }
+    };
+}
+
+// the C API numbers the algorithms in this order, only append new ones
+impl_algorithms!(
+    StdUnstable => "std_unstable", "Std Unstable", parallel: false, simd: false;
+    ParPdqsort => "par_pdqsort", "Parallel PDQSort", parallel: true, simd: false;
+    ParCrumsort => "par_crumsort", "Parallel Crumsort", parallel: true, simd: false;
+    Lomuto => "lomuto", "1-Pivot Lomuto", parallel: false, simd: false;
+    Hoare => "hoare", "1-Pivot Hoare", parallel: false, simd: false;
+    LomutoBlock => "lomuto_block", "1-Pivot Lomuto Block Partition", parallel: false, simd: false;
+    HoareBlock => "hoare_block", "1-Pivot Hoare Block Partition", parallel: false, simd: false;
+    DualPivot => "dual_pivot", "2-Pivot Yaro", parallel: false, simd: false;
+    DualPivotLomutoBlock => "dual_pivot_lomuto_block", "2-Pivot Lomuto Block", parallel: false, simd: false;
+    DualPivotNewBlock => "dual_pivot_new_block", "2-Pivot New Block", parallel: false, simd: false;
+    TriplePivot => "triple_pivot", "3-Pivot Kush", parallel: false, simd: false;
+    QuadPivot => "quad_pivot", "4-Pivot", parallel: false, simd: false;
+    QuadPivotSimd => "quad_pivot_simd", "4-Pivot SIMD", parallel: false, simd: true;
+    PentaPivotSimd => "penta_pivot_simd", "5-Pivot SIMD", parallel: false, simd: true;
+    HexaPivotSimd => "hexa_pivot_simd", "6-Pivot SIMD", parallel: false, simd: true;
+    HeptaPivotSimd => "hepta_pivot_simd", "7-Pivot SIMD", parallel: false, simd: true;
+    OctalPivotSimd => "octal_pivot_simd", "8-Pivot SIMD", parallel: false, simd: true;
+);
+
+impl Algorithm {
+    /// whether `T` can be sorted with this algorithm
+    pub fn supports<T: Sortable>(self) -> bool {
+        T::supports(self)
+    }
+
+    /// # Panics
+    ///
+    /// if the algorithm doesn't support `T`, check with `supports` first
+    pub fn sort<T: Sortable>(self, v: &mut [T]) {
+        T::sort_with(self, v)
+    }
+
+    /// `sort` with the parallel algorithms running on `pool` instead of rayon's global pool
+    pub fn sort_in<T: Sortable>(self, pool: &ThreadPool, v: &mut [T]) {
+        pool.install(|| self.sort(v))
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Algorithm::ALL
+            .iter()
+            .copied()
+            .find(|a| a.name() == s)
+            .ok_or_else(|| format!("unknown algorithm `{}`", s))
+    }
+}
+
+/// Element types the registry can dispatch on
+pub trait Sortable: Ord + Send + Sized {
+    fn supports(algo: Algorithm) -> bool;
+    fn sort_with(algo: Algorithm, v: &mut [Self]);
+}
+
+/// algorithms that only need `T: Ord + Send`
+pub(crate) fn sort_generic<T: Ord + Send>(algo: Algorithm, v: &mut [T]) {
+    match algo {
+        Algorithm::StdUnstable => v.sort_unstable(),
+        Algorithm::ParPdqsort => v.par_sort_unstable(),
+        Algorithm::Lomuto => quick_sort_lomuto_partition(v),
+        Algorithm::Hoare => quick_sort_hoare_partition(v),
+        Algorithm::LomutoBlock => quick_sort_lomuto_partition_block(v),
+        Algorithm::HoareBlock => quick_sort_hoare_partition_block(v),
+        Algorithm::DualPivot => double_pivot_quicksort(v),
+        Algorithm::DualPivotLomutoBlock => double_pivot_quicksort_lomuto_partition_block(v),
+        Algorithm::DualPivotNewBlock => double_pivot_quicksort_new_partition_block(v),
+        Algorithm::TriplePivot => triple_pivot_quicksort(v),
+        Algorithm::QuadPivot => quad_pivot_quicksort(v),
+        _ => panic!("{} does not support {}", algo, core::any::type_name::<T>()),
+    }
+}
+
+fn sort_simd(algo: Algorithm, v: &mut [f32]) {
+    match algo {
+        Algorithm::QuadPivotSimd => quadro_pivot_quicksort_2(v),
+        Algorithm::PentaPivotSimd => penta_pivot_quicksort(v),
+        Algorithm::HexaPivotSimd => hexa_pivot_quicksort(v),
+        Algorithm::HeptaPivotSimd => hepta_pivot_quicksort(v),
+        Algorithm::OctalPivotSimd => octal_pivot_quicksort(v),
+        _ => unreachable!(),
+    }
+}
+
+macro_rules! impl_sortable_copy {
+    ($($t:ty),*) => {
+        $(
+            impl Sortable for $t {
+                fn supports(algo: Algorithm) -> bool {
+                    !algo.is_simd()
+                }
+
+                fn sort_with(algo: Algorithm, v: &mut [Self]) {
+                    match algo {
+                        Algorithm::ParCrumsort => v.par_crumsort(),
+                        _ => sort_generic(algo, v),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_sortable_copy!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, DoubleOrd, (u64, u64));
+// the keys of the Python `sort(key=...)` and `argsort`
+impl_sortable_copy!(
+    Keyed<i8>, Keyed<i16>, Keyed<i32>, Keyed<i64>, Keyed<u8>, Keyed<u16>, Keyed<u32>, Keyed<u64>,
+    Keyed<FloatOrd>, Keyed<DoubleOrd>,
+    Keyed<Reverse<i8>>, Keyed<Reverse<i16>>, Keyed<Reverse<i32>>, Keyed<Reverse<i64>>,
+    Keyed<Reverse<u8>>, Keyed<Reverse<u16>>, Keyed<Reverse<u32>>, Keyed<Reverse<u64>>,
+    Keyed<Reverse<FloatOrd>>, Keyed<Reverse<DoubleOrd>>
+);
+
+impl<const PAD: usize> Sortable for Record<PAD> {
+    fn supports(algo: Algorithm) -> bool {
+        !algo.is_simd()
+    }
+
+    fn sort_with(algo: Algorithm, v: &mut [Self]) {
+        match algo {
+            Algorithm::ParCrumsort => v.par_crumsort(),
+            _ => sort_generic(algo, v),
+        }
+    }
+}
+
+// crumsort needs `Copy`
+macro_rules! impl_sortable_clone {
+    ($($t:ty),*) => {
+        $(
+            impl Sortable for $t {
+                fn supports(algo: Algorithm) -> bool {
+                    !algo.is_simd() && algo != Algorithm::ParCrumsort
+                }
+
+                fn sort_with(algo: Algorithm, v: &mut [Self]) {
+                    sort_generic(algo, v)
+                }
+            }
+        )*
+    };
+}
+
+impl_sortable_clone!(String, Bytes, Tuple);
+impl_sortable_clone!(
+    Keyed<String>, Keyed<Bytes>, Keyed<Tuple>,
+    Keyed<Reverse<String>>, Keyed<Reverse<Bytes>>, Keyed<Reverse<Tuple>>
+);
+
+impl Sortable for FloatOrd {
+    fn supports(_algo: Algorithm) -> bool {
+        true
+    }
+
+    fn sort_with(algo: Algorithm, v: &mut [Self]) {
+        match algo {
+            Algorithm::ParCrumsort => v.par_crumsort(),
+            // FloatOrd is repr(transparent) over f32
+            _ if algo.is_simd() => sort_simd(algo, unsafe { &mut *(v as *mut [FloatOrd] as *mut [f32]) }),
+            _ => sort_generic(algo, v),
+        }
+    }
+}
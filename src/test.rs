@@ -1,13 +1,33 @@
+#![allow(clippy::module_inception)]
+
 #[cfg(test)]
 mod test {
 
+    use crate::assert_sorted;
+    use crate::branchsim::{self, Gshare, TwoBit};
+    #[cfg(feature = "python")]
+    use crate::buffer;
+    use crate::cachesim::{self, Level};
+    use crate::counted;
+    use crate::dist::{self, Dist};
+    use crate::export::{self, Measurement};
+    use crate::external;
+    use crate::ffi::{self, *};
+    use crate::format::{self, Format, Type};
+    use crate::npy;
+    use crate::perf::{self, Event};
+    use crate::pool;
+    use crate::probe::Traced;
     use crate::qsort::*;
-    use crate::ty::FloatOrd;
+    use crate::registry::{Algorithm, Sortable};
+    use crate::timing::{self, Timing};
+    use crate::ty::{Bytes, DoubleOrd, Field, FloatOrd, Keyed, Record, Record128, Record32, Tuple};
     use crate::util::*;
 
     use crumsort::ParCrumSort;
 
     use rayon::prelude::*;
+    use std::sync::Arc;
 
     #[test]
     fn test_u32_and_f32_performance_on_10m_array() {
@@ -15,16 +35,15 @@ mod test {
         let mut copy = arr.clone();
         let dur = time_it(|| copy.par_sort_unstable());
         println!("pdq sort 10m u32 array cost: {:?}ns", dur);
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
 
         let arr: Vec<FloatOrd> = default_vec(10_000_000);
         let mut copy = arr.clone();
         let dur = time_it(|| copy.par_sort_unstable());
         println!("pdq sort 10m f32 array cost: {:?}ns", dur);
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
     }
 
-    // TODO: Run more tests on more types
     #[test]
     fn test_10m_array() {
         fn type_name_of_val<T>() -> &'static str {
@@ -37,7 +56,7 @@ mod test {
                 let mut copy = arr.clone();
                 let dur = time_it(|| copy.sort_unstable());
                 println!("std sort 10m array cost: {:?}ns", dur);
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
 
                 let mut copy = arr.clone();
                 let dur = time_it(|| quick_sort_hoare_partition(&mut copy));
@@ -45,7 +64,7 @@ mod test {
                     "quick sort 1-pivot (hoare partition) 10m array cost: {:?}ns",
                     dur
                 );
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
 
                 let mut copy = arr.clone();
                 let dur = time_it(|| quick_sort_lomuto_partition(&mut copy));
@@ -53,17 +72,17 @@ mod test {
                     "quick sort 1-pivot (lomuto_partition) 10m array cost: {:?}ns",
                     dur
                 );
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
 
                 let mut copy = arr.clone();
                 let dur = time_it(|| double_pivot_quicksort(&mut copy));
                 println!("quick sort 2-pivot 10m array cost: {:?}ns", dur);
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
 
                 let mut copy = arr.clone();
                 let dur = time_it(|| triple_pivot_quicksort(&mut copy));
                 println!("quick sort 3-pivot 10m array cost: {:?}ns", dur);
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
 
                 let mut copy = arr.clone();
                 // transmute copy from Vec<FloatOrd> to Vec<f32>
@@ -72,12 +91,12 @@ mod test {
                     "<!> New impl quick sort 4-pivot 10m array cost: {:?}ns",
                     dur
                 );
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
 
                 let mut copy = arr.clone();
                 let dur = time_it(|| quick_sort_hoare_partition_block(&mut copy));
                 //dbg!(&copy);
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
                 println!(
                     "quick sort 1-pivot (hoare partition block) 10m array cost: {:?}ns",
                     dur
@@ -85,7 +104,7 @@ mod test {
 
                 let mut copy = arr.clone();
                 let dur = time_it(|| quick_sort_lomuto_partition_block(&mut copy));
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
                 println!(
                     "quick sort 1-pivot (lomuto partition block) 10m array cost: {:?}ns",
                     dur
@@ -93,7 +112,7 @@ mod test {
 
                 let mut copy = arr.clone();
                 let dur = time_it(|| double_pivot_quicksort_lomuto_partition_block(&mut copy));
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
                 println!(
                     "quick sort 2-pivot (block partition) 10m array cost: {:?}ns",
                     dur
@@ -101,7 +120,7 @@ mod test {
 
                 let mut copy = arr.clone();
                 let dur = time_it(|| double_pivot_quicksort_new_partition_block(&mut copy));
-                assert_eq!(is_sorted(&copy), true);
+                assert_sorted!(copy);
                 println!(
                     "quick sort 2-pivot (new block partition) 10m array cost: {:?}ns",
                     dur
@@ -117,42 +136,42 @@ mod test {
     fn test_crum_sort_vs_pdq_sort() {
         let mut arr: Vec<FloatOrd> = default_vec(1_000);
         let dur = time_it(|| arr.par_crumsort());
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("crum sort on 1k floats cost: {:?}ns", dur);
 
         let mut arr: Vec<FloatOrd> = default_vec(1_000_000);
         let dur = time_it(|| arr.par_crumsort());
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("crum sort on 1m floats cost: {:?}ns", dur);
 
         let mut arr: Vec<FloatOrd> = default_vec(1_000);
         let dur = time_it(|| arr.par_sort_unstable());
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("pdq sort on 1k floats cost: {:?}ns", dur);
 
         let mut arr: Vec<FloatOrd> = default_vec(1_000_000);
         let dur = time_it(|| arr.par_sort_unstable());
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("pdq sort on 1m floats cost: {:?}ns", dur);
 
         let mut arr: Vec<u32> = default_vec(1_000);
         let dur = time_it(|| arr.par_sort_unstable());
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("pdq sort on 1k u32 cost: {:?}ns", dur);
 
         let mut arr: Vec<u32> = default_vec(1_000_000);
         let dur = time_it(|| arr.par_sort_unstable());
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("pdq sort on 1m u32 cost: {:?}ns", dur);
 
         let mut arr: Vec<u32> = default_vec(1_000);
         let dur = time_it(|| arr.par_crumsort());
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("crumsort on 1k u32 cost: {:?}ns", dur);
 
         let mut arr: Vec<u32> = default_vec(1_000_000);
         let dur = time_it(|| arr.par_crumsort());
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("crumsort on 1m u32 cost: {:?}ns", dur);
     }
 
@@ -162,12 +181,12 @@ mod test {
 
         let mut copy = arr.clone();
         let dur = time_it(|| double_pivot_quicksort(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!("quick sort 2-pivot 10m array cost: {:?}ns", dur);
 
         let mut copy = arr.clone();
         let dur = time_it(|| double_pivot_quicksort_lomuto_partition_block(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!(
             "quick sort 2-pivot (lomuto block partition) 10m array cost: {:?}ns",
             dur
@@ -175,7 +194,7 @@ mod test {
 
         let mut copy = arr.clone();
         let dur = time_it(|| double_pivot_quicksort_new_partition_block(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!(
             "quick sort 2-pivot (new block partition) 10m array cost: {:?}ns",
             dur
@@ -188,12 +207,12 @@ mod test {
 
         let mut copy = arr.clone();
         let dur = time_it(|| double_pivot_quicksort(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!("quick sort 2-pivot 1k array cost: {:?}ns", dur);
 
         let mut copy = arr.clone();
         let dur = time_it(|| double_pivot_quicksort_lomuto_partition_block(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!(
             "quick sort 2-pivot (block partition) 1k array cost: {:?}ns",
             dur
@@ -203,12 +222,12 @@ mod test {
 
         let mut copy = arr.clone();
         let dur = time_it(|| double_pivot_quicksort(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!("quick sort 2-pivot 1m array cost: {:?}ns", dur);
 
         let mut copy = arr.clone();
         let dur = time_it(|| double_pivot_quicksort_lomuto_partition_block(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!(
             "quick sort 2-pivot (block partition) 1m array cost: {:?}ns",
             dur
@@ -220,14 +239,14 @@ mod test {
         let arr: Vec<FloatOrd> = default_vec(10_000_000);
         let mut copy = arr.clone();
         let dur = time_it(|| quick_sort_lomuto_partition(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!(
             "quick sort 1-pivot (lomuto partition) 1m array cost: {:?}ns",
             dur
         );
         let mut copy = arr.clone();
         let dur = time_it(|| quick_sort_lomuto_partition_block(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!(
             "quick sort 1-pivot (lomuto partition block) 1m array cost: {:?}ns",
             dur
@@ -239,7 +258,7 @@ mod test {
         let arr: Vec<FloatOrd> = default_vec(10_000_000);
         let mut copy = arr.clone();
         let dur = time_it(|| quick_sort_hoare_partition(&mut copy));
-        // assert_eq!(is_sorted(&copy), true);
+        // assert_sorted!(copy);
         println!(
             "quick sort 1-pivot (hoare partition) 1m array cost: {:?}ns",
             dur
@@ -247,7 +266,7 @@ mod test {
         let mut copy = arr.clone();
         let dur = time_it(|| quick_sort_hoare_partition_block(&mut copy));
         //dbg!(&copy);
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!(
             "quick sort 1-pivot (hoare partition block) 1m array cost: {:?}ns",
             dur
@@ -258,12 +277,12 @@ mod test {
     fn test_unstable_sort() {
         let mut arr: Vec<f32> = default_vec(1_000);
         let dur = time_it(|| arr.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap()));
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("unstable sort on 1k floats cost: {:?}ns", dur);
 
         let mut arr: Vec<f32> = default_vec(1_000_000);
         let dur = time_it(|| arr.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap()));
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!("unstable sort on 1m floats cost: {:?}ns", dur);
     }
 
@@ -273,7 +292,7 @@ mod test {
 
         let mut copy = arr.clone();
         let dur = time_it(|| quick_sort_hoare_partition(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!(
             "quick sort 1-pivot (hoare partition) already sorted array cost: {:?}ns",
             dur
@@ -281,7 +300,7 @@ mod test {
 
         let mut copy = arr.clone();
         let dur = time_it(|| quick_sort_lomuto_partition(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!(
             "quick sort 1-pivot (lomuto_partition) already sorted array cost: {:?}ns",
             dur
@@ -289,12 +308,12 @@ mod test {
 
         let mut copy = arr.clone();
         let dur = time_it(|| double_pivot_quicksort(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!("quick sort 2-pivot already sorted array cost: {:?}ns", dur);
 
         let mut copy = arr.clone();
         let dur = time_it(|| triple_pivot_quicksort(&mut copy));
-        assert_eq!(is_sorted(&copy), true);
+        assert_sorted!(copy);
         println!("quick sort 3-pivot already sorted array cost: {:?}ns", dur);
     }
 
@@ -302,7 +321,7 @@ mod test {
     fn test_hoare_partition() {
         let mut arr: Vec<FloatOrd> = default_vec(1_000);
         let dur = time_it(|| quick_sort_hoare_partition(&mut arr));
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!(
             "quick sort 1-pivot (hoare partition) 1k array cost: {:?}ns",
             dur
@@ -310,7 +329,7 @@ mod test {
 
         let mut arr: Vec<FloatOrd> = default_vec(1_000_000);
         let dur = time_it(|| quick_sort_hoare_partition(&mut arr));
-        assert_eq!(is_sorted(&arr), true);
+        assert_sorted!(arr);
         println!(
             "quick sort 1-pivot (hoare partition) 1m array cost: {:?}ns",
             dur
@@ -333,11 +352,647 @@ mod test {
     // TODO: test with custom FloatOrd type
     #[test]
     fn test_real_quad_pivot_qsort() {
-        for _ in 0..100 {
-            let mut some_vec: Vec<FloatOrd> = default_vec(5000);
+        for i in 0..100 {
+            // a different array each round, still reproducible from the base seed
+            let mut some_vec: Vec<FloatOrd> = seeded_vec(5000, seed().wrapping_add(i));
             // dbg!(&some_vec);
             quad_pivot_quicksort(&mut some_vec);
-            assert_eq!(is_sorted(&some_vec), true);
+            assert_sorted!(some_vec, "round {}", i);
+        }
+    }
+
+    #[test]
+    fn test_dist_is_deterministic() {
+        for &d in Dist::ALL {
+            let a: Vec<u32> = d.generate(1_000, 42);
+            let b: Vec<u32> = d.generate(1_000, 42);
+            assert_eq!(a, b, "{} differs for the same seed", d);
+            assert_eq!(d.name().parse::<Dist>(), Ok(d));
+        }
+        let a: Vec<u64> = dist::uniform(1_000, 1);
+        let b: Vec<u64> = dist::uniform(1_000, 2);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_dist_shapes() {
+        let n = 1_000;
+        let v: Vec<i32> = dist::sorted(n, 0);
+        assert!(is_sorted(&v));
+        assert_eq!(v[0], i32::MIN);
+        let mut v: Vec<FloatOrd> = dist::reversed(n, 0);
+        v.reverse();
+        assert!(is_sorted(&v));
+
+        let v: Vec<u32> = dist::organ_pipe(n, 0);
+        assert!(is_sorted(&v[..n / 2]));
+        assert_eq!(v[0], v[n - 1]);
+
+        let v: Vec<u32> = dist::pushed_front(n, 0);
+        assert!(is_sorted(&v[1..]));
+        assert_eq!(v[0], *v.iter().max().unwrap());
+        let v: Vec<u32> = dist::pushed_back(n, 0);
+        assert!(is_sorted(&v[..n - 1]));
+        assert_eq!(v[n - 1], *v.iter().min().unwrap());
+
+        let mut v: Vec<u16> = dist::few_unique(n, 0);
+        v.sort_unstable();
+        v.dedup();
+        assert!(v.len() as u64 <= dist::FEW_UNIQUE);
+        let mut v: Vec<i8> = dist::random_01(n, 0);
+        v.sort_unstable();
+        v.dedup();
+        assert_eq!(v, vec![i8::MIN, i8::MAX]);
+        let v: Vec<f64> = dist::all_equal(n, 0);
+        assert!(v.iter().all(|&x| x == v[0]));
+
+        for &d in Dist::ALL {
+            let mut v: Vec<FloatOrd> = d.generate(n, 7);
+            assert_eq!(v.len(), n);
+            assert!(v.iter().all(|x| (0.0..1.0).contains(&x.0)));
+            quick_sort_hoare_partition_block(&mut v);
+            assert!(is_sorted(&v), "{}", d);
+            assert_eq!(d.generate::<u8>(0, 7), vec![]);
+        }
+    }
+
+    #[test]
+    fn test_registry_sorts_every_dist() {
+        // records compare by key only, `intact` checks the rest survived the sort
+        fn check<T: Sortable + dist::Element + Clone + std::fmt::Debug>(intact: fn(&T) -> bool) {
+            for &algo in Algorithm::ALL.iter().filter(|a| a.supports::<T>()) {
+                for &d in Dist::ALL {
+                    let mut v: Vec<T> = d.generate(1_000, seed());
+                    let mut expected = v.clone();
+                    expected.sort();
+                    algo.sort(&mut v);
+                    assert_eq!(v, expected, "{} on {} {}", algo, std::any::type_name::<T>(), d);
+                    assert!(v.iter().all(intact), "{} corrupted {} {}", algo, std::any::type_name::<T>(), d);
+                }
+                assert_eq!(algo.name().parse::<Algorithm>(), Ok(algo));
+            }
+        }
+        fn record<const PAD: usize>(r: &Record<PAD>) -> bool {
+            r.payload.iter().all(|&p| p == r.key)
+        }
+        check::<FloatOrd>(|_| true);
+        check::<DoubleOrd>(|_| true);
+        check::<u32>(|_| true);
+        check::<i64>(|_| true);
+        check::<(u64, u64)>(|_| true);
+        check::<Record32>(record);
+        check::<Record128>(record);
+        check::<String>(|_| true);
+        assert!(!Algorithm::OctalPivotSimd.supports::<u32>());
+        #[cfg(feature = "python")]
+        {
+            assert_eq!(buffer::dtypes(Algorithm::OctalPivotSimd), ["float32"]);
+            assert_eq!(buffer::dtypes(Algorithm::ParCrumsort).len(), 10);
+            // numpy `>i8` and `<i8`, only the one of this machine is sorted in place
+            let (native, swapped) = if cfg!(target_endian = "little") { (b"<q", b">q") } else { (b">q", b"<q") };
+            assert!(buffer::is_native_byte_order(native) && !buffer::is_native_byte_order(swapped));
+            assert!(!buffer::is_native_byte_order(b"!d"));
+            assert!(["q", "=q", "@q", "<", "b"].iter().all(|f| buffer::is_native_byte_order(f.as_bytes())));
+            let format = |f: &[u8]| std::ffi::CString::new(f).unwrap();
+            assert!(<buffer::Native<i64> as pyo3::buffer::Element>::is_compatible_format(&format(native)));
+            assert!(!<buffer::Native<i64> as pyo3::buffer::Element>::is_compatible_format(&format(swapped)));
+            assert!(!<buffer::Native<f64> as pyo3::buffer::Element>::is_compatible_format(&format(native)));
+        }
+    }
+
+    #[test]
+    fn test_export_criterion_results() {
+        let dir = std::env::temp_dir().join(format!("rusorts-export-{}", std::process::id()));
+        let bench = dir.join("criterion/hoare_block_time/u64_few_unique/1000/new");
+        std::fs::create_dir_all(&bench).unwrap();
+        std::fs::write(
+            bench.join("benchmark.json"),
+            r#"{"group_id":"hoare_block_time","function_id":"u64_few_unique","value_str":"1000"}"#,
+        ).unwrap();
+        std::fs::write(
+            bench.join("estimates.json"),
+            r#"{"mean":{"point_estimate":2000.0},"median":{"point_estimate":1500.0},"std_dev":{"point_estimate":100.0}}"#,
+        ).unwrap();
+
+        let rows = export::read_criterion_dir(&dir.join("criterion")).unwrap();
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.method, "1-Pivot Hoare Block Partition");
+        assert_eq!((row.size, row.mean, row.sd, row.median), (1000, 2.0, 0.1, 1.5));
+        assert_eq!((row.measurement, row.ty.as_str(), row.dist.as_str()), (Measurement::Time, "u64", "few_unique"));
+        assert_eq!(row.per_element().mean, 0.002);
+
+        export::export(&rows, &dir.join("out")).unwrap();
+        let csv = std::fs::read_to_string(dir.join("out/time_u64_few_unique.csv")).unwrap();
+        assert_eq!(csv, "Method,Size,Mean,SD,Median\n1-Pivot Hoare Block Partition,1000,2,0.1,1.5\n");
+        let quoted = export::Row { method: "sort, \"fast\"".into(), ..row.clone() };
+        let mut csv = Vec::new();
+        export::write_csv(&mut csv, &[quoted]).unwrap();
+        assert!(String::from_utf8(csv).unwrap().ends_with("\n\"sort, \"\"fast\"\"\",1000,2,0.1,1.5\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_simulation() {
+        let config = cachesim::Config::default();
+        let input: Vec<u64> = dist::uniform(20_000, seed());
+        // 20k u64 are 2500 lines, they fit in the LLC, so it only misses on first touch
+        let data_lines = 20_000 * 8 / 64;
+        for &algo in Algorithm::ALL.iter().filter(|a| a.supports::<Traced<u64>>()) {
+            let mut v = input.clone();
+            let stats = cachesim::simulate(algo, &mut v, &config);
+            assert_sorted!(v, "{}", algo);
+            let mut again = input.clone();
+            assert_eq!(cachesim::simulate(algo, &mut again, &config), stats, "{}", algo);
+            assert!(stats.accesses > 20_000, "{}", algo);
+            assert!(stats.l1_misses >= stats.l2_misses && stats.l2_misses >= stats.llc_misses, "{}", algo);
+            assert!(stats.llc_misses >= data_lines && stats.llc_misses < data_lines + 100, "{}: {:?}", algo, stats);
+            assert_eq!(stats.to_row(algo, "u64", "uniform").mean, stats.llc_misses as f64);
+        }
+
+        // with a 4 KiB LLC the data no longer fits
+        let tiny = cachesim::Config {
+            l1: Level::new(1 << 10, 2, 64),
+            l2: Level::new(2 << 10, 4, 64),
+            llc: Level::new(4 << 10, 4, 64),
+        };
+        let mut v = input.clone();
+        let stats = cachesim::simulate(Algorithm::HoareBlock, &mut v, &tiny);
+        assert!(stats.llc_per_element() > 1.0, "{:?}", stats);
+
+        // without a simulation running traced elements just sort
+        let mut v = input.clone();
+        Algorithm::Hoare.sort(Traced::slice(&mut v));
+        assert_sorted!(v);
+        assert!(!Algorithm::ParPdqsort.supports::<Traced<u64>>());
+    }
+
+    #[test]
+    fn test_branch_simulation() {
+        let input: Vec<u32> = dist::uniform(20_000, seed());
+        for &algo in Algorithm::ALL.iter().filter(|a| a.supports::<Traced<u32>>()) {
+            let mut v = input.clone();
+            let stats = branchsim::simulate(algo, &mut v, TwoBit::default());
+            assert_sorted!(v, "{}", algo);
+            assert!(stats.mispredictions <= stats.branches, "{}", algo);
+            let mut again = input.clone();
+            assert_eq!(branchsim::simulate(algo, &mut again, TwoBit::default()), stats, "{}", algo);
+            let mut v = input.clone();
+            branchsim::simulate(algo, &mut v, Gshare::default());
+            assert_sorted!(v, "{}", algo);
+        }
+
+        // comparisons against a random pivot are a coin flip for the classical partition,
+        // the block partition only branches on whether a block has anything to swap
+        let mut v = input.clone();
+        let classical = branchsim::simulate(Algorithm::Hoare, &mut v, TwoBit::default());
+        let mut v = input.clone();
+        let block = branchsim::simulate(Algorithm::HoareBlock, &mut v, TwoBit::default());
+        assert!(classical.per_element() > 2.0 * block.per_element(), "{:?} vs {:?}", classical, block);
+
+        // sorted input is perfectly predictable once the counters are trained
+        let mut v: Vec<u32> = dist::sorted(2_000, seed());
+        let stats = branchsim::simulate(Algorithm::Lomuto, &mut v, TwoBit::default());
+        assert!(stats.per_element() < 0.1, "{:?}", stats);
+    }
+
+    #[test]
+    fn test_operation_counts() {
+        let input: Vec<i64> = dist::uniform(10_000, seed());
+        for &algo in Algorithm::ALL.iter().filter(|a| a.supports::<counted::Counted<i64>>()) {
+            let mut v = input.clone();
+            let counts = counted::count(algo, &mut v);
+            assert_sorted!(v, "{}", algo);
+            let mut again = input.clone();
+            assert_eq!(counted::count(algo, &mut again), counts, "{}", algo);
+            // n log2 n is about 133k
+            assert!((100_000..400_000).contains(&counts.comparisons), "{}: {:?}", algo, counts);
+            assert!(counts.moves >= 2 * counts.swaps, "{}: {:?}", algo, counts);
+            assert!(counts.scanned >= 10_000 && counts.scanned <= 2 * (counts.comparisons + counts.moves), "{}: {:?}", algo, counts);
+        }
+
+        // insertion sort only, every inversion is one comparison and one swap
+        let mut v = vec![3, 2, 1];
+        let counts = counted::count(Algorithm::Hoare, &mut v);
+        assert_eq!((counts.comparisons, counts.swaps, counts.moves), (3, 3, 6));
+
+        // one partition around 4, then insertion sorts of 0..3 and 5..9 (the debug threshold is 9).
+        // Both scan every element once per pointer passing it, the pivot is a copy and isn't scanned
+        if cfg!(debug_assertions) {
+            for (algo, comparisons, scanned) in [(Algorithm::Hoare, 22, 30), (Algorithm::HoareBlock, 19, 26)] {
+                let mut v = vec![4, 0, 1, 2, 3, 5, 6, 7, 8, 9];
+                let counts = counted::count(algo, &mut v);
+                assert_eq!((counts.comparisons, counts.scanned), (comparisons, scanned), "{}", algo);
+            }
+        }
+
+        let mut v = input.clone();
+        let rows = counted::count(Algorithm::HoareBlock, &mut v).to_rows(Algorithm::HoareBlock, "i64", "uniform");
+        let measurements: Vec<_> = rows.iter().map(|r| r.measurement).collect();
+        assert_eq!(measurements, [Measurement::Comparison, Measurement::Swap, Measurement::Move, Measurement::ScannedElement]);
+        assert_eq!(Measurement::split_group("hoare_scanned_element"), Some(("hoare", Measurement::ScannedElement)));
+    }
+
+    #[test]
+    fn test_measure_falls_back_to_wall_time() {
+        let mut v: Vec<u64> = dist::uniform(100_000, seed());
+        let sample = perf::measure(|v| Algorithm::HoareBlock.sort(v), &mut v, Event::ALL);
+        assert_sorted!(v);
+        assert!(sample.ns > 0);
+        // counters are optional, perf may not be available where the tests run
+        for &(event, n) in &sample.counts {
+            assert!(Event::ALL.contains(&event));
+            assert!(event == Event::CacheMiss || n > 0, "{}", event);
+        }
+        let sample = perf::measure(|v| v.sort(), &mut v, &[]);
+        assert!(sample.counts.is_empty() && sample.get(Event::CpuCycle).is_none());
+        assert_eq!("branch_miss".parse::<Event>(), Ok(Event::BranchMiss));
+        // the counters don't see the rayon threads
+        assert!(perf::events_for(Algorithm::ParPdqsort, Event::ALL).is_empty());
+        assert_eq!(perf::events_for(Algorithm::HoareBlock, Event::ALL), Event::ALL);
+    }
+
+    #[test]
+    fn test_timing_stats() {
+        let timing = Timing::from_samples(vec![12, 10, 11, 13, 10, 1_000, 14, 10]);
+        assert_eq!(timing.outliers, [1_000]);
+        assert_eq!(timing.samples, [10, 10, 10, 11, 12, 13, 14]);
+        assert_eq!((timing.runs(), timing.min(), timing.max()), (8, 10, 14));
+        assert_eq!((timing.mean(), timing.median()), (80.0 / 7.0, 11.0));
+        assert!((timing.sd() - 1.618).abs() < 1e-3);
+        assert_eq!((timing.percentile(0.0), timing.percentile(100.0), timing.percentile(75.0)), (10.0, 14.0, 12.5));
+        // too few samples to tell what an outlier is
+        assert_eq!(Timing::from_samples(vec![1, 1_000]).outliers, []);
+
+        let mut v: Vec<u64> = dist::uniform(10_000, seed());
+        let mut runs = 0;
+        let timing = timing::time_sort(&mut v, 2, 5, |v| {
+            assert!(!is_sorted(v));
+            runs += 1;
+            quick_sort_hoare_partition_block(v);
+        });
+        assert_sorted!(v);
+        assert_eq!((runs, timing.warmup, timing.runs()), (7, 2, 5));
+        assert!(timing.min() > 0);
+
+        let algos = [Algorithm::HoareBlock, Algorithm::OctalPivotSimd];
+        let results = timing::benchmark::<FloatOrd>(&algos, &[100, 1_000], Dist::Uniform, 0, 3, seed());
+        let runs: Vec<_> = results.iter().map(|(algo, size, t)| (*algo, *size, t.runs())).collect();
+        assert_eq!(
+            runs,
+            [(algos[0], 100, 3), (algos[1], 100, 3), (algos[0], 1_000, 3), (algos[1], 1_000, 3)]
+        );
+        // the SIMD sort doesn't support u32
+        assert_eq!(timing::benchmark::<u32>(&algos, &[100], Dist::Uniform, 0, 1, seed()).len(), 1);
+
+        let mut v: Vec<u32> = dist::uniform(1_000, seed());
+        let events = [Event::CpuCycle, Event::Instruction];
+        let measured = timing::measure_sort(&mut v, 1, 4, &events, |v| Algorithm::Hoare.sort(v));
+        assert_sorted!(v);
+        assert_eq!(measured.time.runs(), 4);
+        let rows = measured.to_rows(Algorithm::Hoare, 1_000, "u32", "uniform");
+        // the time in μs, instructions have no CSV
+        assert_eq!(rows[0].measurement, Measurement::Time);
+        assert_eq!(rows[0].median, measured.time.median() * 1e-3);
+        assert_eq!(rows.len(), 1 + measured.events.iter().filter(|(e, _)| *e == Event::CpuCycle).count());
+    }
+
+    #[test]
+    fn test_simd_sorts_on_several_threads() {
+        // every thread has its own arena
+        std::thread::scope(|s| {
+            for (i, &algo) in Algorithm::ALL.iter().filter(|a| a.is_simd()).enumerate() {
+                s.spawn(move || {
+                    let mut v: Vec<FloatOrd> = dist::uniform(100_000, seed().wrapping_add(i as u64));
+                    algo.sort(&mut v);
+                    assert!(is_sorted(&v), "{}", algo);
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_keyed_sort_is_stable() {
+        let keys: Vec<i64> = dist::few_unique(5_000, seed());
+        let mut expected: Vec<_> = (0..keys.len()).collect();
+        expected.sort_by_key(|&i| keys[i]);
+        for &algo in Algorithm::ALL.iter().filter(|a| a.supports::<Keyed<i64>>()) {
+            let mut keyed = Keyed::decorate(keys.iter().copied());
+            algo.sort(&mut keyed);
+            assert!(keyed.iter().map(|k| k.idx).eq(expected.iter().copied()), "{}", algo);
+        }
+        // descending, equal keys still in their original order
+        expected.sort_by_key(|&i| std::cmp::Reverse(keys[i]));
+        let mut keyed = Keyed::decorate(keys.iter().map(|&k| std::cmp::Reverse(k)));
+        Algorithm::QuadPivot.sort(&mut keyed);
+        assert!(keyed.iter().map(|k| k.idx).eq(expected.iter().copied()));
+        assert!(!Algorithm::QuadPivotSimd.supports::<Keyed<DoubleOrd>>());
+        assert!(!Algorithm::ParCrumsort.supports::<Keyed<String>>());
+    }
+
+    #[test]
+    fn test_python_tuple_order() {
+        let tuples: Vec<Tuple> = dist::uniform::<u64>(2_000, seed())
+            .into_iter()
+            .map(|n| {
+                let mut fields = vec![Field::Int(n as i128 % 7 - 3), Field::Str((n % 100).to_string())];
+                fields.truncate(1 + (n % 2) as usize);
+                if n % 3 == 0 {
+                    fields.push(Field::Bytes(Bytes(n.to_le_bytes().to_vec())));
+                }
+                Tuple(fields)
+            })
+            .collect();
+        let mut expected = tuples.clone();
+        expected.sort();
+        // a prefix first, like Python
+        assert!(Tuple(vec![Field::Int(1)]) < Tuple(vec![Field::Int(1), Field::Float(DoubleOrd(-1.0))]));
+        for &algo in Algorithm::ALL.iter().filter(|a| a.supports::<Tuple>()) {
+            let mut v = tuples.clone();
+            algo.sort(&mut v);
+            assert_eq!(v, expected, "{}", algo);
+        }
+    }
+
+    #[test]
+    fn test_thread_pools() {
+        let pool = pool::with_threads(3).unwrap();
+        assert!(Arc::ptr_eq(&pool, &pool::with_threads(3).unwrap()));
+        assert_eq!(pool::install(Some(&pool), rayon::current_num_threads), 3);
+        for algo in [Algorithm::ParPdqsort, Algorithm::ParCrumsort] {
+            let mut v: Vec<u64> = dist::uniform(100_000, seed());
+            algo.sort_in(&pool, &mut v);
+            assert_sorted!(v, "{}", algo);
+        }
+
+        // the pool of 3 threads is the least recently used one after these
+        for threads in 4..4 + pool::MAX_POOLS {
+            pool::with_threads(threads).unwrap();
+        }
+        assert!(!Arc::ptr_eq(&pool, &pool::with_threads(3).unwrap()));
+
+        pool::set_num_threads(Some(2)).unwrap();
+        assert_eq!(pool::num_threads(), 2);
+        let default = pool::get(None).unwrap().unwrap();
+        assert_eq!(pool::install(Some(&default), rayon::current_num_threads), 2);
+        pool::set_num_threads(None).unwrap();
+        assert!(pool::get(None).unwrap().is_none());
+        assert_eq!(pool::num_threads(), rayon::current_num_threads());
+    }
+
+    #[test]
+    fn test_external_sort() {
+        // 16 byte records, an i64 key at byte 8 after the input position
+        let keys: Vec<i64> = dist::few_unique(1_000, seed());
+        let input: Vec<u8> = keys
+            .iter()
+            .enumerate()
+            .flat_map(|(i, k)| (i as u64).to_le_bytes().into_iter().chain(k.to_le_bytes()))
+            .collect();
+        let records = |b: &[u8]| -> Vec<(u64, i64)> {
+            b.chunks(16)
+                .map(|r| (u64::from_le_bytes(r[..8].try_into().unwrap()), i64::from_le_bytes(r[8..].try_into().unwrap())))
+                .collect()
+        };
+        let tmp_dir = std::env::temp_dir().join(format!("rusorts-external-{}", std::process::id()));
+        let sort = |opts: format::Options, memory, record_size| {
+            let config = external::Config { memory, record_size, key_offset: 8, tmp_dir: tmp_dir.clone() };
+            let mut out = vec![];
+            let stats = external::sort_stream(&input[..], &mut out, &opts, &config).unwrap();
+            (records(&out), stats)
+        };
+        let opts = format::Options { algo: Algorithm::DualPivot, ty: Type::I64, ..Default::default() };
+
+        let mut expected = records(&input);
+        expected.sort_by_key(|r| r.1);
+        // stable within and across the runs, 1 record per run needs a second merge pass
+        // a record takes 48 bytes of memory with its sorted copy and key
+        for (memory, runs, passes) in [(1 << 20, 0, 0), (4_800, 10, 0), (1, 1_000, 1)] {
+            let (sorted, stats) = sort(opts.clone(), memory, 16);
+            assert_eq!(sorted, expected, "{} bytes", memory);
+            assert_eq!((stats.records, stats.runs, stats.passes), (1_000, runs, passes));
+        }
+
+        // files in the way of a run are left alone
+        let planted: Vec<_> = (0..3_000).map(|i| tmp_dir.join(format!("rusorts-{}-{}.run", std::process::id(), i))).collect();
+        planted.iter().for_each(|p| std::fs::write(p, b"planted").unwrap());
+        assert_eq!(sort(opts.clone(), 4_800, 16).0, expected);
+        assert!(planted.iter().all(|p| std::fs::read(p).unwrap() == b"planted"));
+        planted.iter().for_each(|p| std::fs::remove_file(p).unwrap());
+
+        // the first record of every key, descending
+        let mut expected = records(&input);
+        expected.sort_by_key(|r| std::cmp::Reverse(r.1));
+        expected.dedup_by_key(|r| r.1);
+        let (sorted, _) = sort(format::Options { reverse: true, unique: true, ..opts.clone() }, 4_800, 16);
+        assert_eq!(sorted, expected);
+
+        let config = external::Config { tmp_dir: tmp_dir.clone(), ..Default::default() };
+        assert!(external::sort_stream(&input[..3], vec![], &opts, &config).is_err());
+        let simd = format::Options { algo: Algorithm::OctalPivotSimd, ..opts.clone() };
+        assert!(external::sort_stream(&input[..], vec![], &simd, &config).is_err());
+        assert_eq!(std::fs::read_dir(&tmp_dir).unwrap().count(), 0, "runs are removed");
+        std::fs::remove_dir(&tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_sort_text_and_binary() {
+        let opts = |f: fn(&mut format::Options)| {
+            let mut opts = format::Options { algo: Algorithm::QuadPivot, ..Default::default() };
+            f(&mut opts);
+            opts
+        };
+        let sort = |text: &str, format, opts| format::sort_text(text, format, &opts);
+
+        assert_eq!(sort("10\n-3\n2.5\n", Format::Lines, opts(|_| ())).unwrap(), "-3\n2.5\n10\n");
+        assert_eq!(sort("10\n9\n", Format::Lines, opts(|o| o.ty = Type::Str)).unwrap(), "10\n9\n");
+        assert_eq!(sort("b\r\na\r\nb\r\n", Format::Lines, opts(|o| o.unique = true)).unwrap(), "a\r\nb\r\n");
+        assert_eq!(sort("", Format::Lines, opts(|_| ())).unwrap(), "");
+        assert_eq!(sort("1\nx\n", Format::Lines, opts(|o| o.ty = Type::I64)), Err("line 2: `x` is not i64".into()));
+
+        let csv = "name;age\n\"a;b\";30\nann;25\nbob;30\n";
+        let by_age = |o: &mut format::Options| {
+            o.delimiter = ';';
+            o.header = true;
+            o.key_column = "age".into();
+            o.reverse = true;
+        };
+        assert_eq!(sort(csv, Format::Csv, opts(by_age)).unwrap(), "name;age\n\"a;b\";30\nbob;30\nann;25\n");
+        assert_eq!(format::fields("\"x,\"\"y\"\"\",z", ','), ["x,\"y\"", "z"]);
+
+        // files are joined on a newline, every CSV header but the first is dropped
+        let chain = |files: &[&'static str], header| {
+            let inputs = files.iter().map(|f| Box::new(f.as_bytes()) as Box<dyn std::io::Read>).collect();
+            let mut text = String::new();
+            format::chain_text(inputs, header).read_to_string(&mut text).unwrap();
+            text
+        };
+        assert_eq!(sort(&chain(&["3\n1", "2"], false), Format::Lines, opts(|_| ())).unwrap(), "1\n2\n3\n");
+        let files = ["name;age\nann;25\n", "name;age", "name;age\r\nbob;30"];
+        assert_eq!(chain(&files, true), "name;age\nann;25\nbob;30\n");
+        assert_eq!(sort(&chain(&files, true), Format::Csv, opts(by_age)).unwrap(), "name;age\nbob;30\nann;25\n");
+
+        let bytes: Vec<u8> = [3i64, -1, 3, 2].iter().flat_map(|x| x.to_le_bytes()).collect();
+        let sorted = format::sort_bytes(bytes, Format::Binary, &opts(|o| {
+            o.ty = Type::I64;
+            o.unique = true;
+        }));
+        let sorted: Vec<i64> = sorted.unwrap().chunks(8).map(|c| i64::from_le_bytes(c.try_into().unwrap())).collect();
+        assert_eq!(sorted, [-1, 2, 3]);
+        assert!(format::sort_bytes(vec![0; 6], Format::Binary, &opts(|o| o.ty = Type::F32)).is_err());
+        let simd = format::Options { algo: Algorithm::OctalPivotSimd, ..Default::default() };
+        assert!(format::sort_text("1\n", Format::Lines, &simd).is_err());
+    }
+
+    #[test]
+    fn test_npy() {
+        let path = std::env::temp_dir().join(format!("rusorts-npy-{}.npy", std::process::id()));
+        let v: Vec<FloatOrd> = Dist::Zipf.generate(1_000, seed());
+        npy::save(&path, &v).unwrap();
+        assert_eq!(npy::load::<FloatOrd>(&path).unwrap(), v);
+        assert!(npy::load::<DoubleOrd>(&path).is_err());
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // version 1, the data starts 64 byte aligned after a newline
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        assert_eq!(bytes.len() - 1_000 * 4, 128);
+        assert_eq!(bytes[127], b'\n');
+
+        // `np.arange(6).reshape(2, 3)[::-1]` with a version 2 header, numpy's for headers over 64K
+        let npy_file = |descr: &str| {
+            let dict = format!("{{'descr': '{}', 'fortran_order': False, 'shape': (2, 3), }}", descr);
+            let mut file = b"\x93NUMPY\x02\x00".to_vec();
+            file.extend((dict.len() as u32 + 1).to_le_bytes());
+            file.extend(format!("{}\n", dict).bytes());
+            file.extend([3i64, 4, 5, 0, 1, 2].iter().flat_map(|x| x.to_le_bytes()));
+            file
+        };
+        let file = npy_file("<i8");
+        let (header, data) = npy::read(&file[..]).unwrap();
+        assert_eq!(header, npy::Header { ty: Type::I64, shape: vec![2, 3] });
+        assert_eq!(data.len(), header.data_len().unwrap());
+
+        // sorted flat, the shape is kept unless `unique` drops elements
+        let sort = |file: Vec<u8>, unique| {
+            let opts = format::Options { unique, ..Default::default() };
+            let (header, data) = npy::read(&format::sort_bytes(file, Format::Npy, &opts).unwrap()[..]).unwrap();
+            (header.shape, data.chunks(8).map(|c| i64::from_le_bytes(c.try_into().unwrap())).collect::<Vec<_>>())
+        };
+        assert_eq!(sort(file.clone(), false), (vec![2, 3], vec![0, 1, 2, 3, 4, 5]));
+        let mut dup = file.clone();
+        dup.truncate(dup.len() - 8);
+        dup.extend(0i64.to_le_bytes());
+        assert_eq!(sort(dup, true), (vec![5], vec![0, 1, 3, 4, 5]));
+
+        for descr in [">i8", "<f2", "|u1"] {
+            assert!(npy::read(&npy_file(descr)[..]).is_err(), "{}", descr);
+        }
+        assert!(npy::read(&file[..file.len() - 1]).is_err());
+        let mut long = file.clone();
+        long.push(0);
+        assert_eq!(npy::read(&long[..]).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        // a shape whose size overflows is rejected before anything is allocated
+        let mut huge = npy_file("<i8");
+        let at = huge.windows(6).position(|w| w == b"(2, 3)").unwrap();
+        huge.splice(at..at + 6, format!("({}, 3)", usize::MAX / 2).bytes());
+        let dict_len = huge.len() as u32 - 12;
+        huge[8..12].copy_from_slice(&dict_len.to_le_bytes());
+        assert_eq!(npy::read(&huge[..]).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        let overflowing = npy::Header { ty: Type::I64, shape: vec![usize::MAX / 4, 2] };
+        assert!(overflowing.len().is_ok() && overflowing.data_len().is_err());
+        assert!(npy::read(&b"\x93NUMPX\x01\x00"[..]).is_err());
+    }
+
+    #[test]
+    fn test_ffi_sorts() {
+        let seed = seed();
+        for (i, &algo) in Algorithm::ALL.iter().enumerate() {
+            let i = i as u32;
+            let name = unsafe { std::ffi::CStr::from_ptr(rusorts_algorithm_name(i)) };
+            assert_eq!(name.to_str(), Ok(algo.name()));
+
+            let mut floats: Vec<f32> = dist::uniform(10_000, seed);
+            assert_eq!(unsafe { rusorts_sort_f32(floats.as_mut_ptr(), floats.len(), i) }, RUSORTS_OK);
+            assert!(floats.windows(2).all(|w| w[0] <= w[1]), "{}", algo);
+
+            let mut ints: Vec<i64> = dist::uniform(10_000, seed);
+            let code = unsafe { rusorts_sort_i64(ints.as_mut_ptr(), ints.len(), i) };
+            if algo.is_simd() {
+                assert_eq!(code, RUSORTS_ERR_UNSUPPORTED);
+            } else {
+                assert_eq!(code, RUSORTS_OK);
+                assert_sorted!(ints, "{}", algo);
+            }
+        }
+        let count = rusorts_algorithm_count();
+        assert_eq!(count as usize, Algorithm::ALL.len());
+        assert!(rusorts_algorithm_name(count).is_null());
+        let mut v = [3u32, 1, 2];
+        assert_eq!(unsafe { rusorts_sort_u32(v.as_mut_ptr(), 3, count) }, RUSORTS_ERR_ALGORITHM);
+        assert_eq!(unsafe { rusorts_sort_i32(std::ptr::null_mut(), 3, 0) }, RUSORTS_ERR_NULL);
+        assert_eq!(unsafe { rusorts_sort_f64(std::ptr::null_mut(), 0, 0) }, RUSORTS_OK);
+        let mut nans = vec![f32::NAN; 1_000];
+        let simd = Algorithm::ALL.iter().position(|a| a.is_simd()).unwrap() as u32;
+        assert_eq!(unsafe { rusorts_sort_f32(nans.as_mut_ptr(), nans.len(), simd) }, RUSORTS_ERR_PANIC);
+    }
+
+    #[test]
+    fn test_ffi_qsort() {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        #[repr(C)]
+        struct Item {
+            key: u32,
+            payload: [u8; 11],
+        }
+        unsafe extern "C" fn by_key(a: *const core::ffi::c_void, b: *const core::ffi::c_void) -> core::ffi::c_int {
+            let (a, b) = (&*a.cast::<Item>(), &*b.cast::<Item>());
+            a.key.cmp(&b.key) as core::ffi::c_int
+        }
+        unsafe extern "C" fn random(_: *const core::ffi::c_void, _: *const core::ffi::c_void) -> core::ffi::c_int {
+            rand::random::<i8>() as core::ffi::c_int
+        }
+
+        let keys: Vec<u32> = dist::uniform(10_000, seed());
+        let items: Vec<Item> = keys.iter().map(|&key| Item { key, payload: [key as u8; 11] }).collect();
+        let mut v = items.clone();
+        unsafe { rusorts_qsort(v.as_mut_ptr().cast(), v.len(), core::mem::size_of::<Item>(), Some(by_key)) };
+        let mut expected = items.clone();
+        expected.sort_by_key(|i| i.key);
+        assert_eq!(v.iter().map(|i| i.key).collect::<Vec<_>>(), expected.iter().map(|i| i.key).collect::<Vec<_>>());
+        assert!(v.iter().all(|i| i.payload == [i.key as u8; 11]));
+
+        // an inconsistent comparator only shuffles the elements
+        let mut v = items.clone();
+        unsafe { rusorts_qsort(v.as_mut_ptr().cast(), v.len(), core::mem::size_of::<Item>(), Some(random)) };
+        v.sort_by_key(|i| i.key);
+        assert_eq!(v, expected);
+        unsafe { rusorts_qsort(std::ptr::null_mut(), 0, 4, None) };
+
+        // quadratic with the first element as the pivot
+        for mut v in [expected.clone(), expected.iter().rev().copied().collect()] {
+            unsafe { rusorts_qsort(v.as_mut_ptr().cast(), v.len(), core::mem::size_of::<Item>(), Some(by_key)) };
+            assert_eq!(v.iter().map(|i| i.key).collect::<Vec<_>>(), expected.iter().map(|i| i.key).collect::<Vec<_>>());
+        }
+        // equal keys never split, the depth limit hands them to the heapsort
+        let mut v: Vec<Item> = items.iter().map(|&i| Item { key: 7, ..i }).collect();
+        unsafe { rusorts_qsort(v.as_mut_ptr().cast(), v.len(), core::mem::size_of::<Item>(), Some(by_key)) };
+        let mut payloads: Vec<_> = v.iter().map(|i| i.payload).collect();
+        payloads.sort();
+        let mut expected: Vec<_> = items.iter().map(|i| i.payload).collect();
+        expected.sort();
+        assert_eq!(payloads, expected);
+    }
+
+    /// `include/rusorts.h` must match `ffi::header()`, `RUSORTS_BLESS=1` rewrites it
+    #[test]
+    fn test_ffi_header() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("include/rusorts.h");
+        let header = ffi::header();
+        if std::env::var_os("RUSORTS_BLESS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &header).unwrap();
         }
+        let checked_in = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(checked_in == header, "{} is outdated, run `RUSORTS_BLESS=1 cargo test ffi`", path.display());
     }
 }
@@ -0,0 +1,201 @@
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::dist::{Dist, Element};
+use crate::export::{Measurement, Row};
+use crate::perf::{self, Event};
+use crate::registry::{Algorithm, Sortable};
+use crate::util::time_it;
+
+/// Samples beyond this many interquartile ranges outside the quartiles are
+/// rejected, the "severe" outliers of criterion
+const OUTLIER_IQRS: f64 = 3.0;
+
+/// Statistics of repeated timed runs, in ns
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timing {
+    /// the kept samples, ascending
+    pub samples: Vec<u64>,
+    /// the rejected samples, ascending
+    pub outliers: Vec<u64>,
+    pub warmup: usize,
+    /// converting the Python list to a Vec, 0 when timed from Rust
+    pub extract_ns: u64,
+    /// converting the sorted Vec back to a Python list, 0 when timed from Rust
+    pub convert_ns: u64,
+}
+
+impl Timing {
+    pub fn from_samples(mut samples: Vec<u64>) -> Timing {
+        samples.sort_unstable();
+        let mut outliers = vec![];
+        if samples.len() >= 4 {
+            let (q1, q3) = (percentile(&samples, 25.0), percentile(&samples, 75.0));
+            let fence = OUTLIER_IQRS * (q3 - q1);
+            let (lo, hi) = (q1 - fence, q3 + fence);
+            (samples, outliers) = samples.into_iter().partition(|&s| (lo..=hi).contains(&(s as f64)));
+        }
+        Timing { samples, outliers, ..Timing::default() }
+    }
+}
+
+/// linear interpolation between the closest ranks of a sorted slice
+fn percentile(sorted: &[u64], p: f64) -> f64 {
+    match sorted.len() {
+        0 => 0.0,
+        n => {
+            let rank = p.clamp(0.0, 100.0) / 100.0 * (n - 1) as f64;
+            let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+            let w = rank - lo as f64;
+            sorted[lo] as f64 * (1.0 - w) + sorted[hi] as f64 * w
+        }
+    }
+}
+
+impl Timing {
+    pub fn runs(&self) -> usize {
+        self.samples.len() + self.outliers.len()
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.samples.iter().sum::<u64>() as f64 / self.samples.len().max(1) as f64
+    }
+
+    /// sample standard deviation
+    pub fn sd(&self) -> f64 {
+        let n = self.samples.len();
+        if n < 2 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        let var = self.samples.iter().map(|&s| (s as f64 - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        var.sqrt()
+    }
+
+    pub fn median(&self) -> f64 {
+        self.percentile(50.0)
+    }
+
+    pub fn min(&self) -> u64 {
+        self.samples.first().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> u64 {
+        self.samples.last().copied().unwrap_or_default()
+    }
+
+    /// `p` in 0 to 100
+    pub fn percentile(&self, p: f64) -> f64 {
+        percentile(&self.samples, p)
+    }
+}
+
+/// Time `sort` on `v` `runs` times after `warmup` untimed runs. Every run
+/// starts from the original order, restoring it is not timed, `v` is left sorted
+///
+/// # Panics
+///
+/// if `runs` is 0
+pub fn time_sort<T: Clone>(v: &mut [T], warmup: usize, runs: usize, mut sort: impl FnMut(&mut [T])) -> Timing {
+    assert!(runs > 0, "at least one run is needed");
+    let original = v.to_vec();
+    for _ in 0..warmup {
+        sort(v);
+        v.clone_from_slice(&original);
+    }
+    let samples = (0..runs)
+        .map(|i| {
+            if i > 0 {
+                v.clone_from_slice(&original);
+            }
+            time_it(|| sort(v))
+        })
+        .collect();
+    Timing { warmup, ..Timing::from_samples(samples) }
+}
+
+/// Statistics of `measure_sort`, the events are counts instead of ns
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Measured {
+    pub time: Timing,
+    /// the events that could be counted in every run
+    pub events: Vec<(Event, Timing)>,
+}
+
+impl Measured {
+    /// rows of the result CSVs, the time in μs like the criterion exports.
+    /// Events without a `Measurement` (instructions) are left out
+    pub fn to_rows(&self, algo: Algorithm, size: usize, ty: &str, dist: &str) -> Vec<Row> {
+        let time = (Measurement::Time, &self.time, 1e-3);
+        let events = self.events.iter().filter_map(|(e, t)| Some((e.name().parse().ok()?, t, 1.0)));
+        Some(time)
+            .into_iter()
+            .chain(events)
+            .map(|(measurement, t, scale)| Row {
+                method: algo.label().to_string(),
+                size,
+                mean: t.mean() * scale,
+                sd: t.sd() * scale,
+                median: t.median() * scale,
+                measurement,
+                ty: ty.to_string(),
+                dist: dist.to_string(),
+            })
+            .collect()
+    }
+}
+
+/// [`time_sort`] that also counts `events` with perf in every run
+pub fn measure_sort<T: Clone>(
+    v: &mut [T],
+    warmup: usize,
+    runs: usize,
+    events: &[Event],
+    mut sort: impl FnMut(&mut [T]),
+) -> Measured {
+    assert!(runs > 0, "at least one run is needed");
+    let original = v.to_vec();
+    for _ in 0..warmup {
+        sort(v);
+        v.clone_from_slice(&original);
+    }
+    let samples: Vec<perf::Sample> = (0..runs)
+        .map(|i| {
+            if i > 0 {
+                v.clone_from_slice(&original);
+            }
+            perf::measure(&mut sort, v, events)
+        })
+        .collect();
+    let time = Timing { warmup, ..Timing::from_samples(samples.iter().map(|s| s.ns).collect()) };
+    let events = events
+        .iter()
+        .filter_map(|&e| {
+            let counts = samples.iter().map(|s| s.get(e)).collect::<Option<Vec<_>>>()?;
+            Some((e, Timing { warmup, ..Timing::from_samples(counts) }))
+        })
+        .collect();
+    Measured { time, events }
+}
+
+/// Time every algorithm of `algos` that supports `T` on `dist` data of every
+/// size. The data is generated from `seed` once per size, only the sorts are timed
+pub fn benchmark<T: Sortable + Element + Clone>(
+    algos: &[Algorithm],
+    sizes: &[usize],
+    dist: Dist,
+    warmup: usize,
+    runs: usize,
+    seed: u64,
+) -> Vec<(Algorithm, usize, Timing)> {
+    let mut results = vec![];
+    for &size in sizes {
+        let data: Vec<T> = dist.generate(size, seed);
+        for &algo in algos.iter().filter(|a| a.supports::<T>()) {
+            let mut v = data.clone();
+            results.push((algo, size, time_sort(&mut v, warmup, runs, |v| algo.sort(v))));
+        }
+    }
+    results
+}
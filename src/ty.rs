@@ -1,36 +1,15 @@
 #![allow(
     clippy::derive_ord_xor_partial_ord,
-    clippy::transmute_float_to_int,
+    unnecessary_transmutes,
 )]
 
 use core::cmp::Ordering;
 use core::ops::Deref;
 
-use pyo3::prelude::*;
 use rand::distributions::Standard;
 use rand::prelude::Distribution;
 
 
-#[derive(FromPyObject)]
-pub(crate) enum OrdNum {
-    #[pyo3(transparent, annotation = "list[int]")]
-    Int(Vec<i32>),
-    #[pyo3(transparent, annotation = "list[float]")]
-    Float(Vec<FloatOrd>),
-}
-
-impl FromPyObject<'_> for FloatOrd {
-    fn extract(ob: &PyAny) -> PyResult<Self> {
-        Ok(FloatOrd(ob.extract()?))
-    }
-}
-
-impl ToPyObject for FloatOrd {
-    fn to_object(&self, py: Python) -> PyObject {
-        self.0.to_object(py)
-    }
-}
-
 #[derive(PartialEq, PartialOrd, Debug, Clone, Copy, Default)]
 #[repr(transparent)]  // guarantees same layout as a single f32
 pub struct FloatOrd(pub f32);
@@ -88,3 +67,131 @@ impl Distribution<FloatOrd> for Standard {
         FloatOrd(rng.gen())
     }
 }
+
+/// f64 counterpart of `FloatOrd`
+#[derive(PartialEq, PartialOrd, Debug, Clone, Copy, Default)]
+#[repr(transparent)]
+pub struct DoubleOrd(pub f64);
+
+impl Deref for DoubleOrd {
+    type Target = f64;
+
+    fn deref(&self) -> &f64 {
+        &self.0
+    }
+}
+
+impl Eq for DoubleOrd {}
+
+impl Ord for DoubleOrd {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // same trick as FloatOrd
+        fn f64_bits(a: f64) -> i64 { unsafe { std::mem::transmute(a) } }
+        let mut a = f64_bits(self.0);
+        let mut b = f64_bits(other.0);
+        if a < 0 { a ^= 0x7fffffffffffffff; }
+        if b < 0 { b ^= 0x7fffffffffffffff; }
+        a.cmp(&b)
+    }
+}
+
+impl Distribution<DoubleOrd> for Standard {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> DoubleOrd {
+        DoubleOrd(rng.gen())
+    }
+}
+
+/// Python `bytes`, a `Vec<u8>` would become a list of ints
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bytes(pub Vec<u8>);
+
+/// A field of a Python tuple, all the tuples of a list have the same kind of
+/// field at the same position
+#[derive(Debug, Clone, PartialEq, Eq, Ord)]
+pub enum Field {
+    Int(i128),
+    Float(DoubleOrd),
+    Str(String),
+    Bytes(Bytes),
+}
+
+impl PartialOrd for Field {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Field {
+    /// name of the Python type
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Field::Int(_) => "int",
+            Field::Float(_) => "float",
+            Field::Str(_) => "str",
+            Field::Bytes(_) => "bytes",
+        }
+    }
+}
+
+/// Python tuple, compared field by field, a shorter prefix first
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Tuple(pub Vec<Field>);
+
+/// A `u64` key followed by `PAD` words of payload, ordered by the key only.
+/// Used to measure how the sorts cope with elements that are expensive to move
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Record<const PAD: usize> {
+    pub key: u64,
+    pub payload: [u64; PAD],
+}
+
+pub type Record32 = Record<3>;
+pub type Record64 = Record<7>;
+pub type Record128 = Record<15>;
+
+impl<const PAD: usize> Record<PAD> {
+    pub fn new(key: u64) -> Self {
+        Record { key, payload: [key; PAD] }
+    }
+}
+
+impl<const PAD: usize> Default for Record<PAD> {
+    fn default() -> Self {
+        Record::new(0)
+    }
+}
+
+impl<const PAD: usize> PartialEq for Record<PAD> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<const PAD: usize> Eq for Record<PAD> {}
+
+impl<const PAD: usize> PartialOrd for Record<PAD> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const PAD: usize> Ord for Record<PAD> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// A sort key decorated with the index of its element, so equal keys keep
+/// their original order whatever the algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Keyed<K> {
+    pub key: K,
+    pub idx: usize,
+}
+
+impl<K> Keyed<K> {
+    pub fn decorate(keys: impl IntoIterator<Item = K>) -> Vec<Keyed<K>> {
+        keys.into_iter().enumerate().map(|(idx, key)| Keyed { key, idx }).collect()
+    }
+}
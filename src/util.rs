@@ -1,9 +1,33 @@
-use rand::{distributions::Standard, Rng, prelude::Distribution};
-use std::time;
+use rand::{distributions::Standard, rngs::StdRng, Rng, SeedableRng, prelude::Distribution};
+use std::{env, sync::Once, time};
+
+/// Env var to override the seed of `default_vec` and the benches
+pub const SEED_ENV: &str = "RUSORTS_SEED";
+pub const DEFAULT_SEED: u64 = 0x5eed;
+
+/// Seed read from `RUSORTS_SEED`, or `DEFAULT_SEED` if it is not set or not
+/// an unsigned integer, the latter warned about once on stderr
+pub fn seed() -> u64 {
+    static WARNED: Once = Once::new();
+    match env::var(SEED_ENV) {
+        Ok(s) => s.trim().parse().unwrap_or_else(|_| {
+            WARNED.call_once(|| {
+                eprintln!("{} must be an unsigned integer, got `{}`, using {}", SEED_ENV, s, DEFAULT_SEED)
+            });
+            DEFAULT_SEED
+        }),
+        Err(_) => DEFAULT_SEED,
+    }
+}
 
 pub fn default_vec<T>(n: usize) -> Vec<T>
 where Standard: Distribution<T> {
-    rand::thread_rng().sample_iter(Standard).take(n).collect()
+    seeded_vec(n, seed())
+}
+
+pub fn seeded_vec<T>(n: usize, seed: u64) -> Vec<T>
+where Standard: Distribution<T> {
+    StdRng::seed_from_u64(seed).sample_iter(Standard).take(n).collect()
 }
 
 pub fn is_sorted<T: PartialOrd>(v: &[T]) -> bool {
@@ -17,35 +41,25 @@ pub fn time_it(f: impl FnOnce()) -> u64 {
     dur.subsec_nanos() as u64 + dur.as_secs() * 1_000_000_000u64
 }
 
+/// `assert!` that a slice is sorted, reporting the seed the data was generated with
 #[macro_export]
-macro_rules! sort_ordnum_f {
-    (trait, $v: expr, $func: ident, $py: ident) => {
-        match $v {
-            OrdNum::Int(mut v) => {
-                let t = time_it(|| (&mut v).$func());
-                let v = PyList::new($py, v);
-                Ok((v, t))
-            }
-            OrdNum::Float(mut v) => {
-                let t = time_it(|| (&mut v).$func());
-                let v = PyList::new($py, v);
-                Ok((v, t))
-            }
-        }
+macro_rules! assert_sorted {
+    ($v: expr) => {
+        assert!(
+            $crate::util::is_sorted(&$v),
+            "not sorted ({}={})",
+            $crate::util::SEED_ENV,
+            $crate::util::seed()
+        )
     };
-    (slice, $v: expr, $func: ident, $py: ident) => {
-        match $v {
-            OrdNum::Int(mut v) => {
-                let t = time_it(|| $func(&mut v));
-                let v = PyList::new($py, v);
-                Ok((v, t))
-            }
-            OrdNum::Float(mut v) => {
-                let t = time_it(|| $func(&mut v));
-                let v = PyList::new($py, v);
-                Ok((v, t))
-            }
-        }
+    ($v: expr, $($arg: tt)+) => {
+        assert!(
+            $crate::util::is_sorted(&$v),
+            "not sorted: {} ({}={})",
+            format_args!($($arg)+),
+            $crate::util::SEED_ENV,
+            $crate::util::seed()
+        )
     };
 }
 
@@ -59,11 +73,17 @@ macro_rules! impl_rotate_n {
         #[inline(always)]
         pub unsafe fn $func<T>(arr: *mut T, idx: [usize; $n]) {
             // cycle the elements in the idx array
-            let tmp = std::ptr::read(arr.add(idx[0]));
+            // the value lives on in the array, so the temporary must not be dropped
+            let tmp = std::mem::ManuallyDrop::new(std::ptr::read(arr.add(idx[0])));
+            <T as $crate::probe::Probe>::load(arr.add(idx[0]));
             for i in 1..$n {
-                std::ptr::copy_nonoverlapping(arr.add(idx[i]), arr.add(idx[i - 1]), 1);
+                // neighbouring indices may coincide, `copy` tolerates src == dst
+                <T as $crate::probe::Probe>::load(arr.add(idx[i]));
+                <T as $crate::probe::Probe>::store(arr.add(idx[i - 1]));
+                std::ptr::copy(arr.add(idx[i]), arr.add(idx[i - 1]), 1);
             }
-            std::ptr::copy_nonoverlapping(&tmp, arr.add(idx[$n - 1]), 1);
+            <T as $crate::probe::Probe>::store(arr.add(idx[$n - 1]));
+            std::ptr::copy_nonoverlapping(&*tmp, arr.add(idx[$n - 1]), 1);
         }
     };
 }